@@ -0,0 +1,6 @@
+ALTER TABLE reminders ADD COLUMN recurrence TEXT;
+ALTER TABLE reminders ADD COLUMN creator_id INTEGER REFERENCES users(id);
+-- Reminders created before this migration only know their followers, assume the first one created it
+UPDATE reminders SET creator_id = (
+    SELECT user_id FROM reminder_user ru WHERE ru.reminder_id = reminders.id ORDER BY rowid LIMIT 1
+);
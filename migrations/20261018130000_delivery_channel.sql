@@ -0,0 +1,2 @@
+-- NULL delivers the reminder by DM, otherwise it's posted in this channel pinging every follower
+ALTER TABLE reminders ADD COLUMN delivery_channel INTEGER REFERENCES channels(id);
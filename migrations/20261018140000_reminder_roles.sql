@@ -0,0 +1,13 @@
+CREATE TABLE roles (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    discord_id INTEGER NOT NULL UNIQUE
+);
+CREATE INDEX role_index ON roles (discord_id);
+-- Role members are looked up when the reminder is delivered, not when it's created
+CREATE TABLE reminder_role (
+    reminder_id INTEGER,
+    role_id INTEGER,
+    PRIMARY KEY (reminder_id, role_id),
+    FOREIGN KEY (reminder_id) REFERENCES reminders(id),
+    FOREIGN KEY (role_id) REFERENCES roles(id)
+);
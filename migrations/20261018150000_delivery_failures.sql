@@ -0,0 +1,3 @@
+ALTER TABLE reminders ADD COLUMN failed_attempts INTEGER NOT NULL DEFAULT 0;
+-- Reminders that kept failing are taken out of the queue, they stay inactive until someone looks at them
+ALTER TABLE reminders ADD COLUMN quarantined BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,22 @@
+-- One row per follower per occurrence of a reminder, retries update the same row
+CREATE TABLE deliveries (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    reminder_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    occurrence INTEGER NOT NULL,
+    -- NULL for DMs, otherwise the delivery or fallback channel the user was pinged in
+    channel_id INTEGER,
+    -- "channel", "dm", "fallback" or "failed"
+    status TEXT NOT NULL,
+    error TEXT,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    attempted_at INTEGER NOT NULL,
+    -- Only set for failed deliveries that will be retried
+    next_attempt_at INTEGER,
+    FOREIGN KEY (reminder_id) REFERENCES reminders(id),
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    FOREIGN KEY (channel_id) REFERENCES channels(id)
+);
+CREATE UNIQUE INDEX delivery_index ON deliveries (reminder_id, user_id, occurrence);
+CREATE INDEX delivery_user_index ON deliveries (user_id);
+CREATE INDEX delivery_retry_index ON deliveries (next_attempt_at);
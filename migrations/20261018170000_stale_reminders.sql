@@ -0,0 +1,5 @@
+-- What to do with reminders that came due while the bot was offline: "deliver", "drop" or "summarize"
+ALTER TABLE guilds ADD COLUMN stale_reminders TEXT NOT NULL DEFAULT 'summarize';
+-- Reminders delivered later than this are stale
+ALTER TABLE guilds ADD COLUMN stale_grace_minutes INTEGER NOT NULL DEFAULT 10;
+-- deliveries.status can now also be "dropped" for stale reminders that were skipped
@@ -0,0 +1,12 @@
+-- Advance warnings each follower gets this many seconds before every occurrence of a reminder
+CREATE TABLE reminder_warnings (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    reminder_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    lead_seconds INTEGER NOT NULL,
+    -- The occurrence this warning was last sent for, recurring reminders warn again for the next one
+    warned_occurrence INTEGER,
+    FOREIGN KEY (reminder_id) REFERENCES reminders(id),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+CREATE UNIQUE INDEX reminder_warning_index ON reminder_warnings (reminder_id, user_id, lead_seconds);
@@ -0,0 +1,17 @@
+-- Persistent reminders are delivered again this many minutes apart until acknowledged, NULL delivers once
+ALTER TABLE reminders ADD COLUMN nag_minutes INTEGER;
+-- One row per follower per occurrence of a persistent reminder, so everyone acknowledges on their own
+CREATE TABLE nags (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    reminder_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    occurrence INTEGER NOT NULL,
+    repeats INTEGER NOT NULL DEFAULT 0,
+    -- NULL once acknowledged or out of repeats
+    next_nag_at INTEGER,
+    acknowledged_at INTEGER,
+    FOREIGN KEY (reminder_id) REFERENCES reminders(id),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+CREATE UNIQUE INDEX nag_index ON nags (reminder_id, user_id, occurrence);
+CREATE INDEX nag_due_index ON nags (next_nag_at);
@@ -0,0 +1,13 @@
+-- "private" reminders can't be followed, "guild" ones can by anyone in their guild and "shared" ones only with the share code
+-- Existing reminders could already be followed by anyone, so they stay that way, except the ones
+-- from DMs, which all share the -1 guild
+ALTER TABLE reminders ADD COLUMN visibility TEXT NOT NULL DEFAULT 'guild';
+UPDATE reminders SET visibility = 'private'
+WHERE id NOT IN (
+    SELECT rg.reminder_id FROM reminder_guild rg JOIN guilds g ON rg.guild_id = g.id
+    WHERE g.discord_id != -1
+);
+ALTER TABLE reminders ADD COLUMN share_code TEXT;
+CREATE UNIQUE INDEX reminder_share_code_index ON reminders (share_code);
+-- Used for new reminders that don't say otherwise
+ALTER TABLE users ADD COLUMN default_visibility TEXT NOT NULL DEFAULT 'private';
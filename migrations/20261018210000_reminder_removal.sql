@@ -0,0 +1,5 @@
+-- When a reminder was deleted, or dropped as broken, before it went off. NULL for ones that finished
+ALTER TABLE reminders ADD COLUMN removed_at INTEGER;
+-- Inactive reminders that still lie in the future can only have been removed
+UPDATE reminders SET removed_at = CAST(strftime('%s', 'now') AS INTEGER)
+WHERE active = 0 AND quarantined = 0 AND timestamp > CAST(strftime('%s', 'now') AS INTEGER);
@@ -0,0 +1,4 @@
+-- Advance warnings are logged alongside the reminders themselves: "reminder" or "warning"
+ALTER TABLE deliveries ADD COLUMN kind TEXT NOT NULL DEFAULT 'reminder';
+DROP INDEX delivery_index;
+CREATE UNIQUE INDEX delivery_index ON deliveries (reminder_id, user_id, occurrence, kind);
@@ -0,0 +1,8 @@
+-- The zone a reminder was created in, so its later occurrences don't move when the creator changes theirs
+ALTER TABLE reminders ADD COLUMN utc_offset INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE reminders ADD COLUMN timezone TEXT;
+-- Existing reminders take the creator's current zone, which is what they were using so far
+UPDATE reminders SET (utc_offset, timezone) = (
+    SELECT utc_offset, timezone FROM users WHERE users.id = reminders.creator_id
+)
+WHERE creator_id IN (SELECT id FROM users);
@@ -0,0 +1,2 @@
+-- A reminder that failed waits until then before it's tried again, instead of on every wake-up
+ALTER TABLE reminders ADD COLUMN next_attempt_at INTEGER;
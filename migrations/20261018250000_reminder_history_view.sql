@@ -0,0 +1,20 @@
+-- Everything reminderhistory lists, so its page and its total count go through the same rows.
+-- Reminders delivered before deliveries were logged only have their own timestamp to go by,
+-- removed ones never went off so they're left out
+CREATE VIEW reminder_history AS
+SELECT d.reminder_id, d.attempted_at AS delivered_at, d.status, d.next_attempt_at, dc.discord_id AS delivery_channel_id,
+    u.discord_id AS user_id, g.discord_id AS guild_id, c.discord_id AS channel_id
+FROM deliveries d
+JOIN users u ON d.user_id = u.id
+LEFT JOIN channels dc ON d.channel_id = dc.id
+JOIN reminder_channel rc ON d.reminder_id = rc.reminder_id JOIN channels c ON rc.channel_id = c.id
+JOIN reminder_guild rg ON d.reminder_id = rg.reminder_id JOIN guilds g ON rg.guild_id = g.id
+WHERE d.kind = 'reminder'
+UNION ALL
+SELECT r.id, r.timestamp, NULL, NULL, NULL, u.discord_id, g.discord_id, c.discord_id
+FROM reminders r
+JOIN reminder_user ru ON r.id = ru.reminder_id JOIN users u ON ru.user_id = u.id
+JOIN reminder_channel rc ON r.id = rc.reminder_id JOIN channels c ON rc.channel_id = c.id
+JOIN reminder_guild rg ON r.id = rg.reminder_id JOIN guilds g ON rg.guild_id = g.id
+WHERE r.active = 0 AND r.removed_at IS NULL
+    AND NOT EXISTS (SELECT 1 FROM deliveries WHERE reminder_id = r.id AND kind = 'reminder');
@@ -1,5 +1,5 @@
 pub mod reminders;
-mod util;
+pub mod util;
 mod utility;
 
 pub fn commands() -> Vec<crate::Command> {
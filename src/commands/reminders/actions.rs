@@ -0,0 +1,349 @@
+use crate::commands::reminders::recurrence::parse_recurrence;
+use crate::commands::reminders::util::{
+    check_user_reminder_count, copy_reminder, guild_from_reminder_id, parse_timestamp,
+    reminder_exists_and_active, reschedule_reminders, set_warnings, user_follows_reminder,
+    user_ids_from_reminder_id, validate_reminder_timestamp,
+};
+use crate::commands::reminders::visibility::{
+    get_reminder_visibility, set_visibility, ReminderVisibility,
+};
+use crate::commands::util::{get_internal_user_id, get_user_timezone, shares_guild};
+use crate::{Data, Error};
+use chrono::Utc;
+use poise::serenity_prelude::{GuildId, UserId};
+use sqlx::{query, query_scalar, SqliteConnection};
+use std::sync::Arc;
+
+// Shared by the commands and the buttons on `reminderinfo`, which don't have a command context
+
+/// How someone found the reminder they want to follow
+pub enum FollowVia {
+    /// By its ID or a button, only works for reminders from that guild that anyone can follow
+    Guild(Option<GuildId>),
+    /// The share code is an invite, so it works from anywhere
+    ShareCode,
+}
+
+/// `lead_times` are the follower's own advance warnings, see `parse_lead_times`
+pub async fn follow_reminder(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, via: FollowVia, lead_times: &[i64],
+) -> Result<(), Error> {
+    if !reminder_exists_and_active(data, reminder_id).await {
+        return Err("U-um... it looks like the reminder doesn't exist anymore... or it's already expired... S-sorry about that!".into());
+    }
+    let user_ids = user_ids_from_reminder_id(data, reminder_id).await?;
+    if user_ids.contains(&user) {
+        return Err("Oh, um... it seems you're already following this reminder... so, I-I can't add it again. Sorry about that!".into());
+    }
+    if let FollowVia::Guild(guild) = via {
+        let guild_id = guild_from_reminder_id(data, reminder_id).await?;
+        if !shares_guild(guild_id, guild) {
+            return Err("Um, it seems this reminder isn't from this guild... S-sorry, but I can't access it here.".into());
+        }
+        let creator = reminder_creator(data, reminder_id).await?;
+        let visibility = get_reminder_visibility(data, reminder_id).await?;
+        if creator != Some(user) && visibility == ReminderVisibility::Shared {
+            return Err(
+                "Um, th-this reminder is invite-only... y-you'll need its share code to follow it."
+                    .into(),
+            );
+        }
+        if creator != Some(user) && visibility == ReminderVisibility::Private {
+            return Err(
+                "Um, th-this reminder is private... S-sorry, but I can't let you follow it.".into(),
+            );
+        }
+    }
+    check_user_reminder_count(data, user).await?;
+
+    let i_user_id = get_internal_user_id(data, user).await?;
+    // The reminder might have been delivered or removed since the checks above
+    let mut tx = data.pool.begin().await?;
+    let followed = query!(
+        r"INSERT INTO reminder_user (reminder_id, user_id)
+        SELECT id, ? FROM reminders WHERE id = ? AND active = 1",
+        i_user_id,
+        reminder_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if followed == 0 {
+        return Err("U-um... it looks like the reminder doesn't exist anymore... or it's already expired... S-sorry about that!".into());
+    }
+    set_warnings(&mut tx, reminder_id, i_user_id, lead_times).await?;
+    tx.commit().await?;
+    reschedule_reminders(data); // A warning might come before anything else
+    Ok(())
+}
+
+/// Changes the advance warnings of a reminder the user already follows
+pub async fn update_warnings(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, lead_times: &[i64],
+) -> Result<(), Error> {
+    if !reminder_exists_and_active(data, reminder_id).await {
+        return Err("U-um... it looks like the reminder doesn't exist anymore... or it's already expired... S-sorry about that!".into());
+    }
+    if !user_ids_from_reminder_id(data, reminder_id).await?.contains(&user) {
+        return Err("Um, it looks like you're not following this reminder... S-sorry, but I can't warn you about it.".into());
+    }
+    let i_user_id = get_internal_user_id(data, user).await?;
+    let mut tx = data.pool.begin().await?;
+    set_warnings(&mut tx, reminder_id, i_user_id, lead_times).await?;
+    tx.commit().await?;
+    reschedule_reminders(data);
+    Ok(())
+}
+
+/// Removes the user from an active reminder and deactivates it when nobody is left to remind.
+/// Returns `None` if they weren't following it, otherwise whether it was deactivated
+async fn remove_follower(
+    conn: &mut SqliteConnection, reminder_id: i64, i_user_id: i64,
+) -> Result<Option<bool>, Error> {
+    let unfollowed = query!(
+        r"DELETE FROM reminder_user WHERE reminder_id = ? AND user_id = ?
+        AND EXISTS (SELECT 1 FROM reminders WHERE id = ? AND active = 1)",
+        reminder_id,
+        i_user_id,
+        reminder_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if unfollowed == 0 {
+        return Ok(None);
+    }
+    query!(
+        "DELETE FROM reminder_warnings WHERE reminder_id = ? AND user_id = ?",
+        reminder_id,
+        i_user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    query!(
+        "UPDATE nags SET next_nag_at = NULL WHERE reminder_id = ? AND user_id = ?",
+        reminder_id,
+        i_user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    // Reminders for a role stay around for the role's members even when nobody follows them
+    let remaining = query_scalar!(
+        r"SELECT (SELECT COUNT(*) FROM reminder_user WHERE reminder_id = ?)
+        + (SELECT COUNT(*) FROM reminder_role WHERE reminder_id = ?)",
+        reminder_id,
+        reminder_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if remaining > 0 {
+        return Ok(Some(false));
+    }
+    let now = Utc::now().timestamp();
+    query!("UPDATE reminders SET active = 0, removed_at = ? WHERE id = ?", now, reminder_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(Some(true))
+}
+
+/// Returns whether the reminder was removed because nobody is left to remind
+pub async fn unfollow_reminder(
+    data: &Arc<Data>, reminder_id: i64, user: UserId,
+) -> Result<bool, Error> {
+    if !reminder_exists_and_active(data, reminder_id).await {
+        return Err("Uh, it seems the reminder doesn't exist or it's already expired... S-sorry, but I can't remove you from it.".into());
+        // TODO: maybe get a better one for this?
+    }
+    let user_ids = user_ids_from_reminder_id(data, reminder_id).await?;
+    if !user_ids.contains(&user) {
+        return Err("Um, it looks like you're not following this reminder... S-sorry, but I can't remove you from it.".into());
+    }
+
+    let i_user_id = get_internal_user_id(data, user).await?;
+    // Count the remaining followers in the same transaction, someone might follow in between
+    let mut tx = data.pool.begin().await?;
+    let Some(removed) = remove_follower(&mut tx, reminder_id, i_user_id).await? else {
+        return Err("Uh, it seems the reminder doesn't exist or it's already expired... S-sorry, but I can't remove you from it.".into());
+    };
+    tx.commit().await?;
+    if removed {
+        reschedule_reminders(data); // This might have been the next reminder
+    }
+    Ok(removed)
+}
+
+/// What happened to each reminder in a bulk unfollow
+#[derive(Default)]
+pub struct BulkUnfollow {
+    pub unfollowed: Vec<i64>,
+    /// Nobody else was following these, so they were removed entirely
+    pub removed: Vec<i64>,
+    /// The user wasn't following these, or they had already expired
+    pub skipped: Vec<i64>,
+}
+
+impl BulkUnfollow {
+    pub fn summary(&self) -> String {
+        let done = self.unfollowed.len() + self.removed.len();
+        if done == 0 {
+            return "Um, y-you weren't following any of those reminders... so I didn't change anything.".to_string();
+        }
+        let mut summary = format!(
+            "O-okay, you'll no longer be notified for {done} reminder{}. I-I hope that's alright!",
+            if done == 1 { "" } else { "s" }
+        );
+        if !self.removed.is_empty() {
+            summary.push_str(&format!(
+                "\nNobody else was tracking {}, so... um, they no longer exist.",
+                format_reminder_ids(&self.removed)
+            ));
+        }
+        if !self.skipped.is_empty() {
+            summary.push_str(&format!(
+                "\nI-I skipped {}, since you weren't following them.",
+                format_reminder_ids(&self.skipped)
+            ));
+        }
+        summary
+    }
+}
+
+fn format_reminder_ids(ids: &[i64]) -> String {
+    ids.iter().map(|id| format!("#{id}")).collect::<Vec<_>>().join(", ")
+}
+
+/// Unfollows all the reminders in one transaction, so the task only has to reschedule once
+pub async fn unfollow_reminders(
+    data: &Arc<Data>, reminder_ids: &[i64], user: UserId,
+) -> Result<BulkUnfollow, Error> {
+    let i_user_id = get_internal_user_id(data, user).await?;
+    let mut result = BulkUnfollow::default();
+    let mut tx = data.pool.begin().await?;
+    for &reminder_id in reminder_ids {
+        match remove_follower(&mut tx, reminder_id, i_user_id).await? {
+            Some(true) => result.removed.push(reminder_id),
+            Some(false) => result.unfollowed.push(reminder_id),
+            None => result.skipped.push(reminder_id),
+        }
+    }
+    tx.commit().await?;
+    if !result.removed.is_empty() {
+        reschedule_reminders(data);
+    }
+    Ok(result)
+}
+
+pub async fn reminder_creator(data: &Arc<Data>, reminder_id: i64) -> Result<Option<UserId>, Error> {
+    let creator_id = query_scalar!(
+        r"SELECT u.discord_id FROM reminders r JOIN users u ON u.id = r.creator_id WHERE r.id = ?",
+        reminder_id
+    )
+    .fetch_optional(&data.pool)
+    .await?;
+    Ok(creator_id.map(|id| UserId::new(id as u64)))
+}
+
+async fn check_creator(data: &Arc<Data>, reminder_id: i64, user: UserId) -> Result<(), Error> {
+    if !reminder_exists_and_active(data, reminder_id).await {
+        return Err("U-um... it looks like the reminder doesn't exist anymore... or it's already expired... S-sorry about that!".into());
+    }
+    if reminder_creator(data, reminder_id).await? != Some(user) {
+        return Err(
+            "Ah, um, only the person who created this reminder can change it... S-sorry!".into()
+        );
+    }
+    Ok(())
+}
+
+/// Changes who can follow the reminder, only its creator may do this. Returns the share code for
+/// shared reminders
+pub async fn update_visibility(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, visibility: ReminderVisibility,
+) -> Result<Option<String>, Error> {
+    check_creator(data, reminder_id, user).await?;
+    let mut conn = data.pool.acquire().await?;
+    set_visibility(&mut conn, reminder_id, visibility).await
+}
+
+/// Removes the reminder for every follower, only its creator may do this
+pub async fn delete_reminder(
+    data: &Arc<Data>, reminder_id: i64, user: UserId,
+) -> Result<(), Error> {
+    check_creator(data, reminder_id, user).await?;
+    let now = Utc::now().timestamp();
+    let mut tx = data.pool.begin().await?;
+    query!("UPDATE reminders SET active = 0, removed_at = ? WHERE id = ?", now, reminder_id)
+        .execute(&mut *tx)
+        .await?;
+    // Nags outlive the reminder going off, but not it being deleted
+    query!("UPDATE nags SET next_nag_at = NULL WHERE reminder_id = ?", reminder_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    reschedule_reminders(data);
+    Ok(())
+}
+
+/// Changes the time and/or message of a reminder, returning the new timestamp and message
+pub async fn update_reminder(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, timestamp: Option<&str>,
+    message: Option<String>,
+) -> Result<(i64, String), Error> {
+    check_creator(data, reminder_id, user).await?;
+    let reminder = query!(
+        "SELECT timestamp, message, recurrence, utc_offset, timezone FROM reminders WHERE id = ?",
+        reminder_id
+    )
+    .fetch_one(&data.pool)
+    .await?;
+
+    // A new time moves the reminder to the zone it was given in, like creating it would
+    let (unix_timestamp, (utc_offset, timezone)) = match timestamp {
+        Some(timestamp) => {
+            let tz = get_user_timezone(data, user).await?;
+            let unix_timestamp = parse_timestamp(data, timestamp, tz)?;
+            validate_reminder_timestamp(unix_timestamp)?;
+            if let Some(recurrence) = &reminder.recurrence {
+                parse_recurrence(&data.regex_cache, recurrence)?.validate(unix_timestamp, tz)?;
+            }
+            (unix_timestamp, tz.to_db())
+        }
+        None => (reminder.timestamp, (reminder.utc_offset, reminder.timezone.as_deref())),
+    };
+    let message = message.unwrap_or(reminder.message);
+
+    query!(
+        "UPDATE reminders SET timestamp = ?, message = ?, utc_offset = ?, timezone = ? WHERE id = ?",
+        unix_timestamp,
+        message,
+        utc_offset,
+        timezone,
+        reminder_id
+    )
+    .execute(&data.pool)
+    .await?;
+    reschedule_reminders(data);
+    Ok((unix_timestamp, message))
+}
+
+/// Copies one of the user's past reminders to a new time, returning the new ID, timestamp
+/// and message
+pub async fn repeat_past_reminder(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, timestamp: &str,
+) -> Result<(i64, i64, String), Error> {
+    check_user_reminder_count(data, user).await?;
+    if !user_follows_reminder(data, reminder_id, user).await? {
+        return Err("Um, it looks like that wasn't one of your reminders... S-sorry, but I can't repeat it for you.".into());
+    }
+
+    let tz = get_user_timezone(data, user).await?;
+    let unix_timestamp = parse_timestamp(data, timestamp, tz)?;
+    validate_reminder_timestamp(unix_timestamp)?;
+    let new_reminder_id = copy_reminder(data, reminder_id, user, unix_timestamp).await?;
+    reschedule_reminders(data);
+
+    let message = query_scalar!("SELECT message FROM reminders WHERE id = ?", new_reminder_id)
+        .fetch_one(&data.pool)
+        .await?;
+    Ok((new_reminder_id, unix_timestamp, message))
+}
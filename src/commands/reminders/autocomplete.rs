@@ -0,0 +1,134 @@
+use crate::commands::util::{force_guild_id, get_author_timezone, UserTimezone};
+use crate::Context;
+use poise::serenity_prelude::AutocompleteChoice;
+use sqlx::query;
+
+const MAX_SUGGESTIONS: usize = 25;
+const MAX_RECENT_REMINDERS: i64 = 100;
+const MAX_LABEL_CHARS: usize = 100;
+
+struct ReminderSuggestion {
+    id: i64,
+    timestamp: i64,
+    message: String,
+}
+
+/// Matches the typed text against the start of the ID or anywhere in the message, returning
+/// labels and IDs
+fn suggest(
+    reminders: Vec<ReminderSuggestion>, partial: &str, tz: UserTimezone,
+) -> Vec<(String, i64)> {
+    let partial = partial.trim().trim_start_matches('#').to_lowercase();
+    reminders
+        .into_iter()
+        .filter(|r| {
+            r.id.to_string().starts_with(&partial) || r.message.to_lowercase().contains(&partial)
+        })
+        .take(MAX_SUGGESTIONS)
+        .map(|r| {
+            // Discord doesn't render <t:..> timestamps in suggestions, so show the user's local time
+            let time = tz
+                .timestamp_to_local(r.timestamp)
+                .map_or(String::new(), |local| local.format("%Y-%m-%d %H:%M").to_string());
+            let label: String = format!("#{} · {time} · {}", r.id, r.message)
+                .chars()
+                .take(MAX_LABEL_CHARS)
+                .collect();
+            (label, r.id)
+        })
+        .collect()
+}
+
+fn choices(suggestions: Vec<(String, i64)>) -> Vec<AutocompleteChoice> {
+    suggestions.into_iter().map(|(label, id)| AutocompleteChoice::new(label, id)).collect()
+}
+
+/// The caller's own active reminders, for `unfollow`. It takes several IDs, so only the last
+/// one is completed and the ones before it are kept
+pub async fn autocomplete_followed_reminders(
+    ctx: Context<'_>, partial: &str,
+) -> Vec<AutocompleteChoice> {
+    let (previous, partial) = partial
+        .rsplit_once(|c: char| c.is_whitespace() || c == ',')
+        .map_or(("", partial), |(previous, last)| (previous.trim_end(), last));
+    let author_id = ctx.author().id.get() as i64;
+    let Ok(reminders) = query!(
+        r"SELECT r.id, r.timestamp, r.message
+        FROM reminders r
+        JOIN reminder_user ru ON r.id = ru.reminder_id JOIN users u on ru.user_id = u.id
+        WHERE u.discord_id = ? AND active = 1 ORDER BY timestamp ASC",
+        author_id
+    )
+    .fetch_all(&ctx.data().pool)
+    .await
+    else {
+        return Vec::new();
+    };
+    let tz = get_author_timezone(&ctx).await.unwrap_or(UserTimezone::from_db(0, None));
+    let reminders = reminders
+        .into_iter()
+        .map(|r| ReminderSuggestion { id: r.id, timestamp: r.timestamp, message: r.message })
+        .collect();
+    suggest(reminders, partial, tz)
+        .into_iter()
+        .map(|(label, id)| match previous {
+            "" => AutocompleteChoice::new(label, id.to_string()),
+            previous => AutocompleteChoice::new(label, format!("{previous} {id}")),
+        })
+        .collect()
+}
+
+/// Active reminders the caller created, for `editreminder`
+pub async fn autocomplete_created_reminders(
+    ctx: Context<'_>, partial: &str,
+) -> Vec<AutocompleteChoice> {
+    let author_id = ctx.author().id.get() as i64;
+    let Ok(reminders) = query!(
+        r"SELECT r.id, r.timestamp, r.message
+        FROM reminders r JOIN users u on r.creator_id = u.id
+        WHERE u.discord_id = ? AND active = 1 ORDER BY timestamp ASC",
+        author_id
+    )
+    .fetch_all(&ctx.data().pool)
+    .await
+    else {
+        return Vec::new();
+    };
+    let tz = get_author_timezone(&ctx).await.unwrap_or(UserTimezone::from_db(0, None));
+    let reminders = reminders
+        .into_iter()
+        .map(|r| ReminderSuggestion { id: r.id, timestamp: r.timestamp, message: r.message })
+        .collect();
+    choices(suggest(reminders, partial, tz))
+}
+
+/// The most recent reminders in this guild anyone can follow, newest first like `follow`'s
+/// default. `follow` also takes share codes, so the values are strings
+pub async fn autocomplete_guild_reminders(
+    ctx: Context<'_>, partial: &str,
+) -> Vec<AutocompleteChoice> {
+    let guild_id = force_guild_id(ctx.guild_id());
+    let Ok(reminders) = query!(
+        r"SELECT r.id, r.timestamp, r.message
+        FROM reminders r
+        JOIN reminder_guild rg ON r.id = rg.reminder_id JOIN guilds g ON rg.guild_id = g.id
+        WHERE active = 1 AND visibility = 'guild' AND g.discord_id = ?
+        ORDER BY created_at DESC LIMIT ?",
+        guild_id,
+        MAX_RECENT_REMINDERS
+    )
+    .fetch_all(&ctx.data().pool)
+    .await
+    else {
+        return Vec::new();
+    };
+    let tz = get_author_timezone(&ctx).await.unwrap_or(UserTimezone::from_db(0, None));
+    let reminders = reminders
+        .into_iter()
+        .map(|r| ReminderSuggestion { id: r.id, timestamp: r.timestamp, message: r.message })
+        .collect();
+    suggest(reminders, partial, tz)
+        .into_iter()
+        .map(|(label, id)| AutocompleteChoice::new(label, id.to_string()))
+        .collect()
+}
@@ -0,0 +1,90 @@
+use crate::commands::reminders::actions::unfollow_reminders;
+use crate::commands::util::force_guild_id;
+use crate::util::confirm;
+use crate::{Context, Error, BOT_COLOR};
+use poise::serenity_prelude::{
+    CreateEmbed, CreateEmbedAuthor, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use sqlx::query;
+use std::fmt::Write;
+
+const MAX_LISTED_REMINDERS: usize = 10;
+
+/// Stop following all your reminders in this server, or only the ones mentioning something
+///
+/// h!clearreminders [search]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "clearreminders",
+    aliases("clearrm", "unfollowall", "forgorall")
+)]
+pub async fn clear_reminders(
+    ctx: Context<'_>,
+    #[description = "Only clear reminders mentioning this"]
+    #[rest]
+    search: Option<String>,
+) -> Result<(), Error> {
+    let author_id = ctx.author().id.get() as i64;
+    let guild_id = force_guild_id(ctx.guild_id());
+    let reminders = query!(
+        r"SELECT r.id, message
+        FROM reminders r
+        JOIN reminder_user ru ON r.id = ru.reminder_id JOIN users u on ru.user_id = u.id
+        JOIN reminder_guild rg on r.id = rg.reminder_id JOIN guilds g on rg.guild_id = g.id
+        WHERE u.discord_id = ?1 AND (g.discord_id = ?2 OR ?2 = -1) AND active = 1
+            AND (?3 IS NULL OR instr(lower(message), lower(?3)) > 0)
+        ORDER BY timestamp ASC",
+        author_id,
+        guild_id,
+        search
+    )
+    .fetch_all(&ctx.data().pool)
+    .await?;
+    if reminders.is_empty() {
+        if search.is_some() {
+            return Err(
+                "Uh, none of your active reminders match that... M-maybe try a different search?"
+                    .into(),
+            );
+        }
+        return Err("Uh, it looks like you don't have any active reminders right now... S-sorry about that!".into());
+    }
+
+    let mut description = format!(
+        "Um, a-are you sure you want to stop following {} reminder{}? Reminders nobody else is tracking will be removed for good...\n",
+        reminders.len(),
+        if reminders.len() == 1 { "" } else { "s" }
+    );
+    for r in reminders.iter().take(MAX_LISTED_REMINDERS) {
+        write!(description, "\n#{} · `{}`", r.id, r.message)?;
+    }
+    if reminders.len() > MAX_LISTED_REMINDERS {
+        write!(description, "\n...and {} more", reminders.len() - MAX_LISTED_REMINDERS)?;
+    }
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title("Clear reminders?")
+        .description(description);
+    let Some(press) = confirm(ctx, embed).await? else {
+        return Ok(());
+    };
+
+    // Only the reminders that were shown, anything created since then stays
+    let reminder_ids: Vec<i64> = reminders.iter().map(|r| r.id).collect();
+    let result = unfollow_reminders(ctx.data(), &reminder_ids, ctx.author().id).await?;
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .description(result.summary());
+    press
+        .create_response(
+            ctx.serenity_context(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new().embed(embed).components(Vec::new()),
+            ),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::commands::reminders::actions::update_reminder;
+use crate::commands::reminders::autocomplete::autocomplete_created_reminders;
+use crate::commands::reminders::util::take_timestamp_words;
+use crate::{Context, Error, BOT_COLOR};
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor};
+use poise::CreateReply;
+
+/// Change the time or message of a reminder you created
+///
+/// h!editreminder <reminder ID> <timestamp or -> [message]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "editreminder",
+    aliases("editrm", "reminderedit", "changereminder")
+)]
+pub async fn edit_reminder(
+    ctx: Context<'_>,
+    #[description = "The reminder to edit"]
+    #[autocomplete = "autocomplete_created_reminders"]
+    reminder_id: u32,
+    #[description = "When you want to be reminded instead, \"-\" keeps the current time"]
+    timestamp: Option<String>,
+    #[description = "What you would like to be reminded of instead"]
+    #[rest]
+    message: Option<String>,
+) -> Result<(), Error> {
+    let reminder_id = reminder_id as i64;
+    let timestamp = timestamp.filter(|t| t != "-");
+    // Only slash commands get the whole time in one argument
+    let (timestamp, message) = match (ctx, timestamp) {
+        (poise::Context::Prefix(_), Some(timestamp)) => {
+            let (timestamp, message) = take_timestamp_words(ctx.data(), timestamp, message);
+            (Some(timestamp), message)
+        }
+        (_, timestamp) => (timestamp, message),
+    };
+    if timestamp.is_none() && message.is_none() {
+        return Err(
+            "Um, y-you didn't tell me what to change... C-could you give me a new time or message?"
+                .into(),
+        );
+    }
+
+    let (unix_timestamp, message) =
+        update_reminder(ctx.data(), reminder_id, ctx.author().id, timestamp.as_deref(), message)
+            .await?;
+
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title(format!("Reminder #{reminder_id} updated."))
+        .description(format!(
+            "O-okay! I'll remind everyone <t:{unix_timestamp}:R>, at <t:{unix_timestamp}:F>, about... uh... {message} instead! I-I hope that's okay!"
+        ));
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -1,22 +1,47 @@
+use crate::commands::reminders::reminder_list::{reminder_list_slash, reminder_list_text};
 use crate::commands::reminders::remindme::{remindme_slash, remindme_text};
 
+pub mod actions;
+mod autocomplete;
+mod clear_reminders;
+mod edit_reminder;
 mod follow;
+mod natural_time;
+pub mod recurrence;
+mod remind_message;
+mod reminder_history;
+mod reminder_info;
 mod reminder_list;
+mod reminder_visibility;
 mod remindme;
+mod repeat_reminder;
 mod unfollow;
 pub mod util;
+pub mod visibility;
 
-pub fn commands() -> [crate::Command; 4] {
+pub fn commands() -> [crate::Command; 11] {
     let remindme = poise::Command {
         slash_action: remindme_slash().slash_action,
         parameters: remindme_slash().parameters,
         ..remindme_text()
     };
+    let reminder_list = poise::Command {
+        slash_action: reminder_list_slash().slash_action,
+        parameters: reminder_list_slash().parameters,
+        ..reminder_list_text()
+    };
 
     [
         remindme,
-        reminder_list::reminder_list(),
+        reminder_list,
         follow::follow(),
         unfollow::unfollow(),
+        edit_reminder::edit_reminder(),
+        remind_message::remind_message(),
+        reminder_history::reminder_history(),
+        repeat_reminder::repeat_reminder(),
+        reminder_info::reminder_info(),
+        clear_reminders::clear_reminders(),
+        reminder_visibility::reminder_visibility(),
     ]
 }
@@ -0,0 +1,282 @@
+use crate::commands::util::UserTimezone;
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime, Weekday};
+
+const DEFAULT_HOUR: u32 = 9;
+/// Words that only glue a phrase together, "friday at 5pm" means the same as "friday 5pm"
+const FILLER_WORDS: [&str; 8] = ["at", "on", "the", "this", "coming", "by", "around", "and"];
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    WEEKDAYS.iter().find(|(name, _)| word.len() >= 3 && name.starts_with(word)).map(|(_, day)| *day)
+}
+
+pub fn is_filler_word(word: &str) -> bool {
+    FILLER_WORDS.contains(&word.to_lowercase().as_str())
+}
+
+fn parse_amount(word: &str) -> Option<i64> {
+    let amount = match word {
+        "a" | "an" | "one" => 1,
+        "two" | "couple" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        "eleven" => 11,
+        "twelve" => 12,
+        "fifteen" => 15,
+        "twenty" => 20,
+        "thirty" => 30,
+        word => word.parse().ok()?,
+    };
+    Some(amount)
+}
+
+fn parse_unit(word: &str) -> Option<i64> {
+    let seconds = match word {
+        "second" | "seconds" | "sec" | "secs" => 1,
+        "minute" | "minutes" | "min" | "mins" => 60,
+        "hour" | "hours" | "hr" | "hrs" => 3600,
+        "day" | "days" => 86400,
+        "week" | "weeks" => 604800,
+        "month" | "months" => 2629800,
+        "year" | "years" => 31557600,
+        _ => return None,
+    };
+    Some(seconds)
+}
+
+/// "2 hours and 30 minutes", "an hour", "half a day"
+fn parse_duration(words: &[&str]) -> Option<i64> {
+    let mut total: i64 = 0;
+    let mut i = 0;
+    while i < words.len() {
+        if words[i] == "and" {
+            i += 1;
+            continue;
+        }
+        let (amount, divisor, used) = match words[i] {
+            "half" if matches!(words.get(i + 1), Some(&"a" | &"an")) => (1, 2, 2),
+            word => (parse_amount(word)?, 1, 1),
+        };
+        let unit = parse_unit(words.get(i + used)?)?;
+        total = total.checked_add(unit.checked_mul(amount)? / divisor)?;
+        i += used + 1;
+    }
+    (total > 0).then_some(total)
+}
+
+/// "5", "5pm", "5:30pm", "17:00", "5.30", with an optional separate "am"/"pm" word
+fn parse_clock(word: &str, meridiem: Option<&str>) -> Option<NaiveTime> {
+    let (word, meridiem) = match ["am", "a.m.", "pm", "p.m."]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix).map(|w| (w, *suffix)))
+    {
+        Some((word, suffix)) => (word, Some(suffix)),
+        None => (word, meridiem),
+    };
+    let (hour, minute) = match word.split_once([':', '.']) {
+        Some((hour, minute)) if minute.len() == 2 => {
+            (hour.parse::<u32>().ok()?, minute.parse().ok()?)
+        }
+        Some(_) => return None,
+        None => (word.parse::<u32>().ok()?, 0),
+    };
+    let hour = match meridiem {
+        Some("am" | "a.m.") if (1..=12).contains(&hour) => hour % 12,
+        Some("pm" | "p.m.") if (1..=12).contains(&hour) => hour % 12 + 12,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn next_weekday(from: NaiveDate, weekday: Weekday, skip_today: bool) -> Option<NaiveDate> {
+    let days_ahead =
+        (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    let days_ahead = if days_ahead == 0 && skip_today { 7 } else { days_ahead };
+    from.checked_add_days(Days::new(days_ahead as u64))
+}
+
+/// The weekday in the calendar week after this one, weeks start on monday.
+/// "next friday" on a wednesday is 9 days away, on a saturday it's the coming friday
+fn weekday_next_week(from: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days_ahead = 7 - from.weekday().num_days_from_monday() + weekday.num_days_from_monday();
+    from.checked_add_days(Days::new(days_ahead as u64))
+}
+
+/// Parses english phrases like "tomorrow at 5pm", "next friday", "friday noon" or "in 2 hours".
+/// `now` is a unix timestamp, wall-clock times are read in the user's time zone
+pub fn parse_natural_time(text: &str, now: i64, tz: UserTimezone) -> Option<i64> {
+    let text = text.trim().to_lowercase();
+    let words: Vec<&str> =
+        text.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty()).collect();
+
+    // Relative phrases don't care about the user's local time
+    match words.as_slice() {
+        ["in", rest @ ..] | [rest @ .., "from", "now"] => return Some(now + parse_duration(rest)?),
+        rest if parse_duration(rest).is_some() => return Some(now + parse_duration(rest)?),
+        _ => {}
+    }
+
+    let local_now = tz.timestamp_to_local(now)?;
+    let today = local_now.date();
+    let mut date: Option<NaiveDate> = None;
+    let mut weekday: Option<Weekday> = None;
+    let mut time: Option<NaiveTime> = None;
+    let mut i = 0;
+    while i < words.len() {
+        let next_word = words.get(i + 1).copied();
+        match words[i] {
+            word if is_filler_word(word) => {}
+            "today" => date = Some(today),
+            "tonight" => {
+                date = Some(today);
+                time = time.or(NaiveTime::from_hms_opt(20, 0, 0));
+            }
+            "tomorrow" | "tmr" | "tmrw" | "tomorow" => date = today.succ_opt(),
+            "day" if next_word == Some("after") && words.get(i + 2) == Some(&"tomorrow") => {
+                date = today.checked_add_days(Days::new(2));
+                i += 2;
+            }
+            "next" => {
+                date = match next_word? {
+                    "week" => today.checked_add_days(Days::new(7)),
+                    "month" => today.checked_add_months(Months::new(1)),
+                    "year" => today.checked_add_months(Months::new(12)),
+                    word => Some(weekday_next_week(today, parse_weekday(word)?)?),
+                };
+                i += 1;
+            }
+            "noon" | "midday" => time = NaiveTime::from_hms_opt(12, 0, 0),
+            // Without a day, the midnight that just passed rolls over to the upcoming one below
+            "midnight" => time = NaiveTime::from_hms_opt(0, 0, 0),
+            "morning" => time = time.or(NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0)),
+            "afternoon" => time = time.or(NaiveTime::from_hms_opt(15, 0, 0)),
+            "evening" => time = time.or(NaiveTime::from_hms_opt(18, 0, 0)),
+            "night" => time = time.or(NaiveTime::from_hms_opt(21, 0, 0)),
+            word if parse_weekday(word).is_some() => weekday = parse_weekday(word),
+            word => {
+                let meridiem = next_word.filter(|w| matches!(*w, "am" | "a.m." | "pm" | "p.m."));
+                let bare_number = !word.contains(|c: char| !c.is_ascii_digit());
+                let after_at = i > 0 && words[i - 1] == "at";
+                if bare_number && meridiem.is_none() && !after_at {
+                    return None; // "5" on its own is too ambiguous
+                }
+                time = Some(parse_clock(word, meridiem)?);
+                if meridiem.is_some() {
+                    i += 1;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if date.is_none() && weekday.is_none() && time.is_none() {
+        return None;
+    }
+    let explicit_date = date.is_some();
+    let date = match (date, weekday) {
+        (Some(date), _) => date,
+        (None, Some(weekday)) => next_weekday(today, weekday, false)?,
+        (None, None) => today,
+    };
+    let mut local = date.and_time(time.unwrap_or(NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0)?));
+    if local <= local_now && !explicit_date {
+        // "5pm" after 5pm means tomorrow, "friday" on a friday evening means next week
+        local = local.checked_add_days(Days::new(if weekday.is_some() { 7 } else { 1 }))?;
+    }
+    tz.local_to_timestamp(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn utc(datetime: &str) -> i64 {
+        NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap().and_utc().timestamp()
+    }
+
+    #[test]
+    fn parses_relative_to_a_fixed_now() {
+        // A wednesday morning
+        let now = utc("2026-10-14 10:00");
+        let cases = [
+            ("tomorrow", Some("2026-10-15 09:00")),
+            ("tomorrow at 5pm", Some("2026-10-15 17:00")),
+            ("day after tomorrow", Some("2026-10-16 09:00")),
+            ("tonight", Some("2026-10-14 20:00")),
+            ("friday", Some("2026-10-16 09:00")),
+            ("this friday", Some("2026-10-16 09:00")),
+            ("next friday", Some("2026-10-23 09:00")),
+            ("next monday", Some("2026-10-19 09:00")),
+            ("next wednesday", Some("2026-10-21 09:00")),
+            ("wednesday", Some("2026-10-21 09:00")),
+            ("wednesday 5pm", Some("2026-10-14 17:00")),
+            ("friday noon", Some("2026-10-16 12:00")),
+            ("noon", Some("2026-10-14 12:00")),
+            ("midnight", Some("2026-10-15 00:00")),
+            ("friday midnight", Some("2026-10-16 00:00")),
+            ("midnight on friday", Some("2026-10-16 00:00")),
+            ("tomorrow midnight", Some("2026-10-15 00:00")),
+            ("12am", Some("2026-10-15 00:00")),
+            ("12pm", Some("2026-10-14 12:00")),
+            ("12 p.m.", Some("2026-10-14 12:00")),
+            ("9am", Some("2026-10-15 09:00")),
+            ("at 5", Some("2026-10-15 05:00")),
+            ("17:30", Some("2026-10-14 17:30")),
+            ("in 2 hours", Some("2026-10-14 12:00")),
+            ("in an hour and 30 minutes", Some("2026-10-14 11:30")),
+            ("in half a day", Some("2026-10-14 22:00")),
+            ("2 days from now", Some("2026-10-16 10:00")),
+            ("a week from now", Some("2026-10-21 10:00")),
+            ("", None),
+            ("5", None),
+            ("banana", None),
+            ("next", None),
+            ("next banana", None),
+            ("in", None),
+            ("in 0 hours", None),
+            ("in 2 bananas", None),
+            ("13pm", None),
+            ("0am", None),
+            ("5:3pm", None),
+            ("friday the 5", None),
+        ];
+        let tz = UserTimezone::from_db(0, None);
+        for (text, expected) in cases {
+            assert_eq!(parse_natural_time(text, now, tz), expected.map(utc), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn next_weekday_is_in_the_following_week() {
+        // A saturday, next week starts on monday
+        let now = utc("2026-10-17 10:00");
+        let tz = UserTimezone::from_db(0, None);
+        assert_eq!(parse_natural_time("next friday", now, tz), Some(utc("2026-10-23 09:00")));
+        assert_eq!(parse_natural_time("next saturday", now, tz), Some(utc("2026-10-24 09:00")));
+        assert_eq!(parse_natural_time("next monday", now, tz), Some(utc("2026-10-19 09:00")));
+    }
+
+    #[test]
+    fn reads_wall_clock_times_in_the_users_zone() {
+        let now = utc("2026-10-14 10:00");
+        let tz = UserTimezone::from_db(120, None);
+        assert_eq!(parse_natural_time("tomorrow at 5pm", now, tz), Some(utc("2026-10-15 15:00")));
+        assert_eq!(parse_natural_time("in 2 hours", now, tz), Some(utc("2026-10-14 12:00")));
+    }
+}
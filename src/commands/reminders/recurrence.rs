@@ -0,0 +1,502 @@
+use crate::commands::reminders::util::parse_naivetime;
+use crate::commands::util::{matches_to_vecint, UserTimezone};
+use crate::{Error, RegexCache};
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike};
+
+const MIN_RECURRENCE_SECONDS: i64 = 3600;
+const ALL_DAYS: u8 = 0b1111111;
+const WEEKDAYS: [&str; 7] =
+    ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+pub enum Recurrence {
+    /// Every n seconds, optionally moved to a fixed local time
+    Interval { seconds: i64, time: Option<NaiveTime> },
+    /// Bitmask of weekdays starting at monday, 0 means the weekday of the previous occurrence
+    Weekly { days: u8, time: Option<NaiveTime> },
+    /// Day of the month every n months, 0 means the day of the previous occurrence. New rules get
+    /// the day written in by `anchor_recurrence`
+    Monthly { day: u32, months: u32, time: Option<NaiveTime> },
+    /// minute hour day-of-month month day-of-week
+    Cron(CronSchedule),
+}
+
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn recurrence_error() -> Error {
+    "Um, I-I don't really understand how often you want to be reminded... C-could you try something like \"every 2 days\", \"every weekday at 09:00\" or \"1st of each month\"?".into()
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let value = range.parse().ok()?;
+            (value, if step > 1 { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Some(bits)
+}
+
+fn parse_cron(rule: &str) -> Option<CronSchedule> {
+    let fields: Vec<&str> = rule.split_whitespace().collect();
+    if fields.len() != 5 || !fields.iter().all(|f| f.chars().all(|c| "0123456789*,-/".contains(c)))
+    {
+        return None;
+    }
+    let mut weekdays = parse_cron_field(fields[4], 0, 7)?;
+    if weekdays & (1 << 7) != 0 {
+        weekdays = (weekdays | 1) & !(1 << 7); // both 0 and 7 mean sunday
+    }
+    Some(CronSchedule {
+        minutes: parse_cron_field(fields[0], 0, 59)?,
+        hours: parse_cron_field(fields[1], 0, 23)?,
+        days: parse_cron_field(fields[2], 1, 31)?,
+        months: parse_cron_field(fields[3], 1, 12)?,
+        weekdays,
+        any_day: fields[2].starts_with('*'),
+        any_weekday: fields[4].starts_with('*'),
+    })
+}
+
+impl CronSchedule {
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if !self.any_day && !self.any_weekday {
+            day || weekday // cron matches either field when both are restricted
+        } else {
+            day && weekday
+        }
+    }
+
+    fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start =
+            after.date().and_hms_opt(after.hour(), after.minute(), 0)? + TimeDelta::minutes(1);
+        for offset in 0..=366 {
+            let date = start.date().checked_add_days(Days::new(offset))?;
+            if !self.matches_date(date) {
+                continue;
+            }
+            let (first_hour, first_minute) =
+                if offset == 0 { (start.hour(), start.minute()) } else { (0, 0) };
+            for hour in (first_hour..24).filter(|h| self.hours & (1 << h) != 0) {
+                let first_minute = if hour == first_hour { first_minute } else { 0 };
+                if let Some(minute) = (first_minute..60).find(|m| self.minutes & (1 << m) != 0) {
+                    return date.and_hms_opt(hour, minute, 0);
+                }
+            }
+        }
+        None
+    }
+}
+
+fn parse_weekday(word: &str) -> Option<u32> {
+    WEEKDAYS
+        .iter()
+        .position(|name| {
+            word.strip_suffix('s').is_some_and(|w| w == *name)
+                || (word.len() >= 3 && name.starts_with(word))
+        })
+        .map(|day| day as u32)
+}
+
+fn parse_ordinal(word: &str) -> Option<u32> {
+    if word == "last" {
+        return Some(31); // clamped to the last day of every month
+    }
+    let number = ["st", "nd", "rd", "th"].iter().find_map(|suffix| word.strip_suffix(suffix))?;
+    number.parse::<u32>().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn parse_unit(word: &str) -> Option<i64> {
+    match word {
+        "minute" | "minutes" | "min" | "mins" | "m" => Some(60),
+        "hour" | "hours" | "h" => Some(3600),
+        "day" | "days" | "d" => Some(86400),
+        "week" | "weeks" | "w" => Some(604800),
+        _ => None,
+    }
+}
+
+fn parse_monthly(words: &[&str], time: Option<NaiveTime>) -> Option<Recurrence> {
+    let mut day = None;
+    let mut months = 1;
+    let mut has_month = false;
+    for word in words {
+        match *word {
+            "of" | "each" | "every" | "on" | "day" => {}
+            "month" | "months" | "monthly" => has_month = true,
+            "year" | "years" | "yearly" | "annually" => {
+                has_month = true;
+                months *= 12;
+            }
+            word => {
+                if let Some(ordinal) = parse_ordinal(word) {
+                    day = Some(ordinal);
+                } else {
+                    months *= word.parse::<u32>().ok().filter(|n| *n > 0)?;
+                }
+            }
+        }
+    }
+    if !has_month {
+        return None;
+    }
+    Some(Recurrence::Monthly { day: day.unwrap_or(0), months, time })
+}
+
+fn parse_time_of_day(regex_cache: &RegexCache, time: &str) -> Result<NaiveTime, Error> {
+    let Some(captures) = regex_cache.time.captures(time.trim()) else {
+        return Err(recurrence_error());
+    };
+    parse_naivetime(&matches_to_vecint(&captures)?, 0)
+}
+
+/// Parses rules like "every 2 days", "every weekday at 09:00", "1st of each month" or a cron expression
+pub fn parse_recurrence(regex_cache: &RegexCache, rule: &str) -> Result<Recurrence, Error> {
+    let rule = rule.trim().to_lowercase();
+    let rule = rule.strip_prefix("cron ").unwrap_or(&rule).trim();
+    if let Some(schedule) = parse_cron(rule) {
+        return Ok(Recurrence::Cron(schedule));
+    }
+
+    let (rule, time) = match rule.rsplit_once(" at ") {
+        Some((rule, time)) => (rule, Some(parse_time_of_day(regex_cache, time)?)),
+        None => (rule, None),
+    };
+    let words: Vec<&str> = rule
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty() && !matches!(*w, "and" | "the"))
+        .collect();
+    let words = match words.first() {
+        Some(&"every" | &"each") => &words[1..],
+        _ => &words[..],
+    };
+
+    let recurrence = match words {
+        [] => return Err(recurrence_error()),
+        ["hourly"] => Recurrence::Interval { seconds: 3600, time: None },
+        ["daily" | "day"] => Recurrence::Weekly { days: ALL_DAYS, time },
+        ["weekly" | "week"] => Recurrence::Weekly { days: 0, time },
+        ["monthly" | "month"] => Recurrence::Monthly { day: 0, months: 1, time },
+        ["yearly" | "year" | "annually"] => Recurrence::Monthly { day: 0, months: 12, time },
+        ["weekday" | "weekdays"] => Recurrence::Weekly { days: 0b0011111, time },
+        ["weekend" | "weekends"] => Recurrence::Weekly { days: 0b1100000, time },
+        [unit] if parse_unit(unit).is_some() => {
+            Recurrence::Interval { seconds: parse_unit(unit).unwrap_or_default(), time }
+        }
+        [amount, unit]
+            if amount.parse::<i64>().is_ok_and(|a| a > 0) && parse_unit(unit).is_some() =>
+        {
+            let amount = amount.parse::<i64>().unwrap_or_default();
+            let Some(seconds) = amount.checked_mul(parse_unit(unit).unwrap_or_default()) else {
+                return Err(recurrence_error());
+            };
+            Recurrence::Interval { seconds, time }
+        }
+        words if words.iter().all(|w| parse_weekday(w).is_some()) => {
+            let days =
+                words.iter().filter_map(|w| parse_weekday(w)).fold(0, |days, d| days | 1 << d);
+            Recurrence::Weekly { days, time }
+        }
+        words => parse_monthly(words, time).ok_or_else(recurrence_error)?,
+    };
+
+    if let Recurrence::Interval { seconds, time: Some(_) } = recurrence {
+        if seconds % 86400 != 0 {
+            return Err("Um, I can only use a time of day when the reminder repeats every few days or weeks... S-sorry!".into());
+        }
+    }
+    Ok(recurrence)
+}
+
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (1, n) if n != 11 => "st",
+        (2, n) if n != 12 => "nd",
+        (3, n) if n != 13 => "rd",
+        _ => "th",
+    };
+    format!("{day}{suffix}")
+}
+
+/// Writes the day of the first occurrence into "monthly" or "yearly" rules. Following the previous
+/// occurrence instead would keep a day that got clamped to a shorter month, 31st to 28th for good
+pub fn anchor_recurrence(
+    regex_cache: &RegexCache, rule: &str, first: i64, tz: UserTimezone,
+) -> Result<String, Error> {
+    let Recurrence::Monthly { day: 0, .. } = parse_recurrence(regex_cache, rule)? else {
+        return Ok(rule.to_string());
+    };
+    let day = ordinal(tz.timestamp_to_local(first).ok_or_else(recurrence_error)?.day());
+    let rule = rule.trim().to_lowercase();
+    Ok(match rule.rsplit_once(" at ") {
+        Some((rule, time)) => format!("{rule} on the {day} at {time}"),
+        None => format!("{rule} on the {day}"),
+    })
+}
+
+fn clamped_date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    (1..=day).rev().find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+}
+
+impl Recurrence {
+    /// Finds the first occurrence after both `previous` and `now`, in the user's local time
+    pub fn next_occurrence(&self, previous: i64, now: i64, tz: UserTimezone) -> Option<i64> {
+        let after = previous.max(now);
+        if let Recurrence::Interval { seconds, time: None } = self {
+            // Plain intervals don't care about wall-clock time, so DST can't shift them
+            let skipped = (after - previous) / seconds + 1;
+            return previous.checked_add(skipped.checked_mul(*seconds)?);
+        }
+
+        let local_previous = tz.timestamp_to_local(previous)?;
+        let mut local_after = tz.timestamp_to_local(after)?;
+        loop {
+            let next = self.next_local(local_previous, local_after)?;
+            let timestamp = tz.local_to_timestamp(next)?;
+            if timestamp > after {
+                return Some(timestamp);
+            }
+            local_after = next; // a wall-clock time that happens twice and already passed
+        }
+    }
+
+    fn next_local(
+        &self, local_previous: NaiveDateTime, local_after: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        let next = match self {
+            Recurrence::Interval { seconds, time } => {
+                let skipped = (local_after - local_previous).num_seconds() / seconds + 1;
+                let mut next =
+                    local_previous + TimeDelta::try_seconds(skipped.checked_mul(*seconds)?)?;
+                if let Some(time) = time {
+                    next = next.date().and_time(*time);
+                    while next <= local_after {
+                        next += TimeDelta::try_seconds(*seconds)?;
+                    }
+                }
+                next
+            }
+            Recurrence::Weekly { days, time } => {
+                let days = match days {
+                    0 => 1 << local_previous.weekday().num_days_from_monday(),
+                    days => *days,
+                };
+                let time = time.unwrap_or(local_previous.time());
+                (0..=7)
+                    .filter_map(|d| local_after.date().checked_add_days(Days::new(d)))
+                    .map(|date| date.and_time(time))
+                    .find(|dt| {
+                        days & (1 << dt.weekday().num_days_from_monday()) != 0 && *dt > local_after
+                    })?
+            }
+            Recurrence::Monthly { day, months, time } => {
+                let day = match day {
+                    0 => local_previous.day(),
+                    day => *day,
+                };
+                let time = time.unwrap_or(local_previous.time());
+                let first_month = local_previous.year() * 12 + local_previous.month0() as i32;
+                (0..1200)
+                    .map(|i| first_month + i * *months as i32)
+                    .filter_map(|m| clamped_date(m / 12, m as u32 % 12 + 1, day))
+                    .map(|date| date.and_time(time))
+                    .find(|dt| *dt > local_after)?
+            }
+            Recurrence::Cron(schedule) => schedule.next_after(local_after)?,
+        };
+        Some(next)
+    }
+
+    /// Makes sure the rule keeps firing and doesn't fire too often
+    pub fn validate(&self, first: i64, tz: UserTimezone) -> Result<(), Error> {
+        let mut previous = first;
+        for _ in 0..5 {
+            let Some(next) = self.next_occurrence(previous, first, tz) else {
+                return Err("Um, it looks like this schedule would never happen again... C-could you check it?".into());
+            };
+            if next - previous < MIN_RECURRENCE_SECONDS {
+                return Err("U-um, that's a bit too often for me... C-could you make it repeat at most once an hour?".into());
+            }
+            previous = next;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMSTERDAM: Option<&str> = Some("Europe/Amsterdam");
+
+    fn timestamp(tz: UserTimezone, datetime: &str) -> i64 {
+        let local = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap();
+        tz.local_to_timestamp(local).unwrap()
+    }
+
+    /// The next few occurrences of a rule in local time, anchored like `remindme` does it
+    fn occurrences(rule: &str, first: &str, tz: UserTimezone, count: usize) -> Vec<String> {
+        let regex_cache = RegexCache::new();
+        let first = timestamp(tz, first);
+        let rule = anchor_recurrence(&regex_cache, rule, first, tz).unwrap();
+        let recurrence = parse_recurrence(&regex_cache, &rule).unwrap();
+        let mut previous = first;
+        (0..count)
+            .map(|_| {
+                previous = recurrence.next_occurrence(previous, previous, tz).unwrap();
+                tz.timestamp_to_local(previous).unwrap().format("%Y-%m-%d %H:%M").to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_rules() {
+        let regex_cache = RegexCache::new();
+        let parse = |rule| parse_recurrence(&regex_cache, rule);
+        assert!(matches!(parse("hourly"), Ok(Recurrence::Interval { seconds: 3600, time: None })));
+        assert!(matches!(
+            parse("every 2 days at 9:30"),
+            Ok(Recurrence::Interval { seconds: 172800, time: Some(_) })
+        ));
+        assert!(matches!(parse("Every weekday"), Ok(Recurrence::Weekly { days: 0b0011111, .. })));
+        assert!(matches!(
+            parse("monday, wednesday and fridays"),
+            Ok(Recurrence::Weekly { days: 0b0010101, .. })
+        ));
+        assert!(matches!(parse("monthly"), Ok(Recurrence::Monthly { day: 0, months: 1, .. })));
+        assert!(matches!(
+            parse("last day of every 3 months"),
+            Ok(Recurrence::Monthly { day: 31, months: 3, .. })
+        ));
+        assert!(matches!(
+            parse("yearly on the 29th at 08:00"),
+            Ok(Recurrence::Monthly { day: 29, months: 12, time: Some(_) })
+        ));
+        assert!(matches!(parse("cron 0 9 * * 1-5"), Ok(Recurrence::Cron(_))));
+
+        assert!(parse("").is_err());
+        assert!(parse("sometimes").is_err());
+        assert!(parse("every 0 days").is_err());
+        assert!(parse("32nd of each month").is_err());
+        assert!(parse("every 3 hours at 09:00").is_err());
+        assert!(parse("daily at 25:00").is_err());
+        assert!(parse("60 * * * *").is_err());
+    }
+
+    #[test]
+    fn anchors_only_rules_without_a_day() {
+        let regex_cache = RegexCache::new();
+        let tz = UserTimezone::from_db(0, None);
+        let first = timestamp(tz, "2027-01-31 23:30");
+        let anchor = |rule| anchor_recurrence(&regex_cache, rule, first, tz).unwrap();
+        assert_eq!(anchor("monthly"), "monthly on the 31st");
+        assert_eq!(anchor("Every 2 months at 23:30"), "every 2 months on the 31st at 23:30");
+        assert_eq!(anchor("1st of each month"), "1st of each month");
+        assert_eq!(anchor("weekly"), "weekly");
+        // The day is the creator's, not UTC's
+        let tz = UserTimezone::from_db(60, None);
+        assert_eq!(
+            anchor_recurrence(&regex_cache, "yearly", first, tz).unwrap(),
+            "yearly on the 1st"
+        );
+    }
+
+    #[test]
+    fn cron_matches_either_day_field() {
+        let both = parse_cron("0 0 13 * 5").unwrap();
+        let date = |d| NaiveDate::from_ymd_opt(2026, 11, d).unwrap();
+        assert!(both.matches_date(date(13))); // a friday the 13th
+        assert!(both.matches_date(date(6))); // just a friday
+        assert!(!both.matches_date(date(12)));
+        let weekdays = parse_cron("30 9 * 1,7 mon-fri");
+        assert!(weekdays.is_none());
+        let weekdays = parse_cron("30 9 * 1,7 1-5").unwrap();
+        assert!(!weekdays.matches_date(date(2))); // november
+        let sundays = parse_cron("0 12 * * 7").unwrap();
+        assert!(sundays.matches_date(NaiveDate::from_ymd_opt(2026, 11, 1).unwrap()));
+
+        let every_15 = parse_cron("*/15 8-9 * * *").unwrap();
+        let after = NaiveDate::from_ymd_opt(2026, 11, 2).unwrap().and_hms_opt(9, 45, 30).unwrap();
+        let next = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap().and_hms_opt(8, 0, 0).unwrap();
+        assert_eq!(every_15.next_after(after), Some(next));
+    }
+
+    #[test]
+    fn month_end_days_come_back_after_short_months() {
+        let utc = UserTimezone::from_db(0, None);
+        assert_eq!(
+            occurrences("monthly", "2027-01-31 09:00", utc, 4),
+            ["2027-02-28 09:00", "2027-03-31 09:00", "2027-04-30 09:00", "2027-05-31 09:00"]
+        );
+        assert_eq!(
+            occurrences("yearly", "2028-02-29 09:00", utc, 4),
+            ["2029-02-28 09:00", "2030-02-28 09:00", "2031-02-28 09:00", "2032-02-29 09:00"]
+        );
+        assert_eq!(
+            occurrences("last day of each month", "2027-01-31 09:00", utc, 2),
+            ["2027-02-28 09:00", "2027-03-31 09:00"]
+        );
+    }
+
+    #[test]
+    fn local_times_survive_dst() {
+        let tz = UserTimezone::from_db(0, AMSTERDAM);
+        assert_eq!(
+            occurrences("daily", "2026-03-28 09:00", tz, 2),
+            ["2026-03-29 09:00", "2026-03-30 09:00"]
+        );
+        assert_eq!(
+            occurrences("weekly", "2026-10-20 09:00", tz, 2),
+            ["2026-10-27 09:00", "2026-11-03 09:00"]
+        );
+        // Plain intervals count real time instead
+        assert_eq!(
+            occurrences("every 24 hours", "2026-03-28 09:00", tz, 2),
+            ["2026-03-29 10:00", "2026-03-30 10:00"]
+        );
+        // 02:30 doesn't exist on the 29th, so it's read like the clock hadn't jumped yet
+        assert_eq!(
+            occurrences("daily at 02:30", "2026-03-28 02:30", tz, 2),
+            ["2026-03-29 03:30", "2026-03-30 02:30"]
+        );
+    }
+
+    #[test]
+    fn repeated_hours_fire_once() {
+        let tz = UserTimezone::from_db(0, AMSTERDAM);
+        let regex_cache = RegexCache::new();
+        let recurrence = parse_recurrence(&regex_cache, "daily at 02:30").unwrap();
+        let first = timestamp(tz, "2026-10-24 02:30");
+        let next = recurrence.next_occurrence(first, first, tz).unwrap();
+        assert_eq!(next, timestamp(tz, "2026-10-25 02:30"));
+        let after = recurrence.next_occurrence(next, next + 3600, tz).unwrap();
+        assert_eq!(after, timestamp(tz, "2026-10-26 02:30"));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::commands::reminders::remindme::{remindme, RemindmeOptions};
+use crate::commands::reminders::util::check_author_reminder_count;
+use crate::{ApplicationContext, Error};
+use poise::serenity_prelude::Message;
+use poise::Modal;
+
+#[derive(Modal)]
+#[name = "Remind me about this message"]
+struct RemindMessageModal {
+    #[name = "When do you want to be reminded?"]
+    #[placeholder = "tomorrow at 5pm, 2h30m, 2026-12-24 18:00"]
+    timestamp: String,
+}
+
+/// Create a reminder about a message, linking back to it
+#[poise::command(
+    context_menu_command = "Remind me about this",
+    check = "check_author_reminder_count"
+)]
+pub async fn remind_message(ctx: ApplicationContext<'_>, message: Message) -> Result<(), Error> {
+    let Some(modal) = RemindMessageModal::execute(ctx).await? else {
+        return Ok(()); // the modal timed out, nothing to remind about
+    };
+    let options = RemindmeOptions { reference: Some(message), ..Default::default() };
+    remindme(ctx.into(), modal.timestamp, None, options).await
+}
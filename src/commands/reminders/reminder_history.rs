@@ -0,0 +1,150 @@
+use crate::commands::reminders::actions::repeat_past_reminder;
+use crate::commands::reminders::repeat_reminder::repeat_embed;
+use crate::commands::util::force_guild_id;
+use crate::util::{url_guild_id, Page, Paginator};
+use crate::{Context, Error};
+use poise::serenity_prelude::{ComponentInteraction, CreateInteractionResponseFollowup};
+use poise::Modal;
+use sqlx::{query, query_scalar};
+use std::fmt::Write;
+use std::time::Duration;
+
+const PAGE_ITEMS: usize = 6;
+const MAX_LISTED_FOLLOWERS: usize = 5;
+const REPEAT_MODAL_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Modal)]
+#[name = "Repeat this reminder"]
+struct RepeatModal {
+    #[name = "When do you want to be reminded again?"]
+    #[placeholder = "tomorrow at 5pm, 2h30m, 2026-12-24 18:00"]
+    timestamp: String,
+}
+
+async fn history_page(
+    ctx: Context<'_>, author_id: i64, guild_id: i64, page_index: usize,
+) -> Result<Page, Error> {
+    let limit = PAGE_ITEMS as i64;
+    let offset = (page_index * PAGE_ITEMS) as i64;
+    let past_reminders = query!(
+        r#"SELECT h.reminder_id AS "id!: i64", h.delivered_at AS "delivered_at!: i64", h.status AS "status?: String", h.next_attempt_at AS "next_attempt_at?: i64", r.message AS "message!: String", h.delivery_channel_id AS "delivery_channel_id?: i64", h.guild_id AS "guild_id!: i64", h.channel_id AS "channel_id!: i64", r.message_id AS "message_id!: i64", r.quarantined AS "quarantined!: bool",
+            (SELECT GROUP_CONCAT(fu.discord_id, ' ') FROM reminder_user fr JOIN users fu ON fr.user_id = fu.id WHERE fr.reminder_id = r.id) AS "followers?: String"
+        FROM reminder_history h JOIN reminders r ON h.reminder_id = r.id
+        WHERE h.user_id = ?1 AND (h.guild_id = ?2 OR ?2 = -1)
+        ORDER BY h.delivered_at DESC, h.reminder_id DESC LIMIT ?3 OFFSET ?4"#,
+        author_id, guild_id, limit, offset
+    )
+    .fetch_all(&ctx.data().pool)
+    .await?;
+    let author = author_id.to_string();
+    let mut page = Page::default();
+    for r in &past_reminders {
+        let status = match (r.status.as_deref(), r.delivery_channel_id, r.next_attempt_at) {
+            (Some("failed"), _, Some(next_attempt)) => {
+                format!("**Could not be delivered to you**, I'll try again <t:{next_attempt}:R>")
+            }
+            (Some("failed"), _, None) => "**Could not be delivered to you**".to_string(),
+            (None, _, _) if r.quarantined => "**Could not be delivered**".to_string(),
+            (Some("dropped"), _, _) => "Skipped, I-I was away when it was due".to_string(),
+            (Some("fallback"), Some(channel), _) => {
+                format!("Pinged in <#{channel}>, your DMs were closed")
+            }
+            (_, Some(channel), _) => format!("Posted in <#{channel}>"),
+            (Some(_), None, _) => "Sent by DM".to_string(),
+            (None, None, _) => "Delivered".to_string(),
+        };
+        let mut history_string = format!("ID: {0} · <t:{1}:f> · `{2}` ([Context](https://hitori.discord.com/channels/{3}/{4}/{5})) · {6}", r.id, r.delivered_at, r.message, url_guild_id(r.guild_id), r.channel_id, r.message_id, status);
+        let followers: Vec<&str> = r
+            .followers
+            .as_deref()
+            .unwrap_or_default()
+            .split(' ')
+            .filter(|f| !f.is_empty() && *f != author)
+            .collect();
+        if !followers.is_empty() {
+            history_string.push_str(" · With");
+            for follower in followers.iter().take(MAX_LISTED_FOLLOWERS) {
+                write!(history_string, " <@{follower}>")?;
+            }
+            if followers.len() > MAX_LISTED_FOLLOWERS {
+                write!(history_string, " and {} more", followers.len() - MAX_LISTED_FOLLOWERS)?;
+            }
+        }
+        page.lines.push(history_string);
+        // Repeating reminders show up once per delivery, but can only be picked once
+        let value = r.id.to_string();
+        if !page.items.iter().any(|(_, v)| *v == value) {
+            page.items.push((format!("#{} · {}", r.id, r.message), value));
+        }
+    }
+
+    Ok(page)
+}
+
+/// Shows the reminders you were sent, and the ones that didn't reach you
+///
+/// h!reminderhistory [page]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "reminderhistory",
+    aliases("pastreminders", "remindhistory", "completed"),
+    discard_spare_arguments
+)]
+pub async fn reminder_history(
+    ctx: Context<'_>, #[description = "The page to start on"] start_page: Option<usize>,
+) -> Result<(), Error> {
+    let author_id = ctx.author().id.get() as i64;
+    let guild_id = force_guild_id(ctx.guild_id());
+    let total = query_scalar!(
+        r#"SELECT COUNT(*) AS "count!: i64" FROM reminder_history
+        WHERE user_id = ?1 AND (guild_id = ?2 OR ?2 = -1)"#,
+        author_id,
+        guild_id
+    )
+    .fetch_one(&ctx.data().pool)
+    .await?;
+    if total == 0 {
+        return Err("Uh, I-I haven't reminded you of anything yet... S-sorry about that!".into());
+    }
+
+    let title = format!(
+        "Reminder history for {} (\"{}repeatreminder <ID> <time>\" to repeat one)",
+        ctx.author().name,
+        ctx.prefix()
+    );
+    Paginator::new(title, total as usize, PAGE_ITEMS, move |page| {
+        Box::pin(history_page(ctx, author_id, guild_id, page))
+    })
+    .select("Repeat one of these reminders...", move |press, value| {
+        Box::pin(repeat_from_history(ctx, press, value))
+    })
+    .run(ctx, start_page.unwrap_or_default())
+    .await
+}
+
+async fn repeat_from_history(
+    ctx: Context<'_>, press: ComponentInteraction, value: String,
+) -> Result<(), Error> {
+    let reminder_id = value.parse::<i64>()?;
+    let modal = poise::execute_modal_on_component_interaction::<RepeatModal>(
+        ctx,
+        press.clone(),
+        None,
+        Some(REPEAT_MODAL_TIMEOUT),
+    )
+    .await?;
+    let Some(modal) = modal else {
+        return Ok(()); // the modal timed out, nothing to repeat
+    };
+    let (new_reminder_id, unix_timestamp, message) =
+        repeat_past_reminder(ctx.data(), reminder_id, ctx.author().id, &modal.timestamp).await?;
+    let embed = repeat_embed(ctx.author(), new_reminder_id, unix_timestamp, &message);
+    press
+        .create_followup(
+            ctx.serenity_context(),
+            CreateInteractionResponseFollowup::new().embed(embed),
+        )
+        .await?;
+    Ok(())
+}
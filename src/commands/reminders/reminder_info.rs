@@ -0,0 +1,148 @@
+use crate::commands::reminders::actions::reminder_creator;
+use crate::commands::reminders::util::role_ids_from_reminder_id;
+use crate::commands::reminders::visibility::ReminderVisibility;
+use crate::commands::util::shares_guild;
+use crate::events::reminders::reminder_info_components;
+use crate::util::url_guild_id;
+use crate::{Context, Error, BOT_COLOR};
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor, GuildId, UserId};
+use poise::{ChoiceParameter, CreateReply};
+use sqlx::query;
+use std::fmt::Write;
+
+const MAX_LISTED_FOLLOWERS: usize = 15;
+
+/// Shows everything about a reminder
+///
+/// h!reminderinfo <reminder ID>
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "reminderinfo",
+    aliases("rminfo", "showreminder", "aboutreminder"),
+    discard_spare_arguments
+)]
+pub async fn reminder_info(
+    ctx: Context<'_>, #[description = "The reminder to show"] reminder_id: u32,
+) -> Result<(), Error> {
+    let reminder_id = reminder_id as i64;
+    let Ok(r) = query!(
+        r"SELECT r.message, r.timestamp, r.created_at, r.message_id, r.recurrence, r.active, r.quarantined, r.visibility, c.discord_id AS channel_id, g.discord_id AS guild_id, dc.discord_id AS delivery_channel
+        FROM reminders r
+        JOIN reminder_channel rc ON r.id = rc.reminder_id JOIN channels c on rc.channel_id = c.id
+        JOIN reminder_guild rg on r.id = rg.reminder_id JOIN guilds g on rg.guild_id = g.id
+        LEFT JOIN channels dc ON dc.id = r.delivery_channel
+        WHERE r.id = ?",
+        reminder_id
+    )
+    .fetch_one(&ctx.data().pool)
+    .await
+    else {
+        return Err("U-um... it looks like the reminder doesn't exist... S-sorry about that!".into());
+    };
+    let follower_ids: Vec<UserId> = query!(
+        r"SELECT u.discord_id FROM reminder_user ru JOIN users u ON ru.user_id = u.id WHERE ru.reminder_id = ?",
+        reminder_id
+    )
+    .fetch_all(&ctx.data().pool)
+    .await?
+    .into_iter()
+    .map(|f| UserId::new(f.discord_id as u64))
+    .collect();
+    let role_ids = role_ids_from_reminder_id(ctx.data(), reminder_id).await?;
+    let creator = reminder_creator(ctx.data(), reminder_id).await?;
+
+    // Same scoping as follow, reminders from other guilds or that not just anyone can follow are
+    // only visible to the people in them
+    let author_id = ctx.author().id;
+    let following = follower_ids.contains(&author_id);
+    let same_guild = shares_guild(r.guild_id, ctx.guild_id());
+    let visibility = ReminderVisibility::from_db(&r.visibility);
+    if !following && creator != Some(author_id) {
+        if !same_guild {
+            return Err("Um, it seems this reminder isn't from this guild... S-sorry, but I can't access it here.".into());
+        }
+        if visibility != ReminderVisibility::Guild {
+            return Err(
+                "Um, th-this reminder is private... S-sorry, but I can't show it to you.".into()
+            );
+        }
+    }
+
+    let mut followers = String::new();
+    for follower_id in follower_ids.iter().take(MAX_LISTED_FOLLOWERS) {
+        let name = match follower_id.to_user(ctx).await {
+            Ok(user) => user.name,
+            Err(_) => format!("<@{follower_id}>"),
+        };
+        write!(followers, "{name}, ")?;
+    }
+    let mut followers = followers.trim_end_matches(", ").to_string();
+    if follower_ids.len() > MAX_LISTED_FOLLOWERS {
+        write!(followers, " and {} more", follower_ids.len() - MAX_LISTED_FOLLOWERS)?;
+    }
+    for role_id in &role_ids {
+        write!(followers, " <@&{role_id}>")?;
+    }
+    if followers.is_empty() {
+        followers = "Nobody".to_string();
+    }
+
+    let origin = if r.guild_id == -1 {
+        "Direct messages".to_string()
+    } else {
+        let guild_id = GuildId::new(r.guild_id as u64);
+        guild_id.name(ctx.cache()).unwrap_or_else(|| guild_id.to_string())
+    };
+    let creator_name = match creator {
+        Some(creator) => format!("<@{creator}>"),
+        None => "Unknown".to_string(),
+    };
+    let status = match (r.active, r.quarantined) {
+        (true, _) => "Active",
+        (false, true) => "Couldn't be delivered",
+        (false, false) => "Delivered or removed",
+    };
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title(format!("Reminder #{reminder_id}"))
+        .description(r.message)
+        .field("Created by", creator_name, true)
+        .field("Followers", followers, true)
+        .field("Status", status, true)
+        .field(
+            "Origin",
+            format!(
+                "{origin}, <#{0}> ([Context](https://hitori.discord.com/channels/{1}/{0}/{2}))",
+                r.channel_id,
+                url_guild_id(r.guild_id),
+                r.message_id
+            ),
+            true,
+        )
+        .field("Created", format!("<t:{0}:F> (<t:{0}:R>)", r.created_at), true)
+        .field("Due", format!("<t:{0}:F> (<t:{0}:R>)", r.timestamp), true)
+        .field("Repeats", r.recurrence.unwrap_or("No".to_string()), true)
+        .field("Visibility", visibility.name(), true)
+        .field(
+            "Delivered to",
+            r.delivery_channel.map_or("DMs".to_string(), |channel| format!("<#{channel}>")),
+            true,
+        );
+
+    let mut reply = CreateReply::default().embed(embed);
+    if r.active {
+        let components = reminder_info_components(
+            reminder_id,
+            same_guild && visibility == ReminderVisibility::Guild,
+            following,
+            creator == Some(author_id),
+        );
+        if let Some(components) = components {
+            reply = reply.components(vec![components]);
+        }
+    }
+    ctx.send(reply).await?;
+    Ok(())
+}
@@ -1,39 +1,126 @@
-use crate::commands::util::force_guild_id;
+use crate::commands::reminders::util::{parse_timestamp, take_flag, take_switch};
+use crate::commands::util::{force_guild_id, get_author_timezone};
 use crate::util::{paginate, url_guild_id};
 use crate::{Context, Error};
 use sqlx::query;
 
 const PAGE_ITEMS: usize = 8;
 
-/// Shows your list of reminders
-///
-/// what the fuck
-#[poise::command(
-    slash_command,
-    prefix_command,
-    rename = "reminderlist",
-    aliases("reminders"),
-    discard_spare_arguments
-)]
-pub async fn reminder_list(
-    ctx: Context<'_>, #[description = "The page to start on"] start_page: Option<usize>,
+#[derive(poise::ChoiceParameter, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReminderOwner {
+    #[default]
+    #[name = "All reminders"]
+    All,
+    #[name = "Created by me"]
+    Created,
+    #[name = "Following someone else's"]
+    Following,
+}
+
+#[derive(poise::ChoiceParameter, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReminderScope {
+    #[default]
+    #[name = "This server"]
+    Here,
+    #[name = "All servers"]
+    Everywhere,
+}
+
+#[derive(poise::ChoiceParameter, Clone, Copy, Default)]
+pub enum ReminderSort {
+    #[default]
+    #[name = "Due soonest"]
+    DueSoonest,
+    #[name = "Due latest"]
+    DueLatest,
+    #[name = "Newest"]
+    Newest,
+    #[name = "Oldest"]
+    Oldest,
+}
+
+impl ReminderSort {
+    fn from_flag(flag: &str) -> Result<Self, Error> {
+        match flag.to_lowercase().as_str() {
+            "due" | "soonest" => Ok(ReminderSort::DueSoonest),
+            "latest" => Ok(ReminderSort::DueLatest),
+            "newest" | "new" => Ok(ReminderSort::Newest),
+            "oldest" | "old" => Ok(ReminderSort::Oldest),
+            _ => Err("Um, I-I don't know that sort order... C-could you use due, latest, newest or oldest?".into()),
+        }
+    }
+}
+
+struct ReminderFilters {
+    search: Option<String>,
+    due_before: Option<String>,
+    due_after: Option<String>,
+    owner: ReminderOwner,
+    scope: ReminderScope,
+    sort: ReminderSort,
+}
+
+impl ReminderFilters {
+    fn is_filtered(&self) -> bool {
+        self.search.is_some()
+            || self.due_before.is_some()
+            || self.due_after.is_some()
+            || self.owner != ReminderOwner::All
+    }
+}
+
+async fn list_reminders(
+    ctx: Context<'_>, filters: ReminderFilters, start_page: usize,
 ) -> Result<(), Error> {
     let author_id = ctx.author().id.get() as i64;
     let guild_id = force_guild_id(ctx.guild_id());
-    let reminders = query!(
-        r"SELECT r.id, message, timestamp, c.discord_id AS channel_id, g.discord_id AS guild_id, message_id
+    let everywhere = filters.scope == ReminderScope::Everywhere;
+    let created = filters.owner == ReminderOwner::Created;
+    let following = filters.owner == ReminderOwner::Following;
+    let tz = get_author_timezone(&ctx).await?;
+    let due_before = match &filters.due_before {
+        Some(before) => Some(parse_timestamp(ctx.data(), before, tz)?),
+        None => None,
+    };
+    let due_after = match &filters.due_after {
+        Some(after) => Some(parse_timestamp(ctx.data(), after, tz)?),
+        None => None,
+    };
+    let search = filters.search.as_deref();
+    // Reminders you created but stopped following only show up when asking for your own
+    let mut reminders = query!(
+        r"SELECT r.id, message, timestamp, created_at, c.discord_id AS channel_id, g.discord_id AS guild_id, message_id
         FROM reminders r
-        JOIN reminder_user ru ON r.id = ru.reminder_id JOIN users u on ru.user_id = u.id
+        JOIN users me ON me.discord_id = ?1
         JOIN reminder_channel rc ON r.id = rc.reminder_id JOIN channels c on rc.channel_id = c.id
         JOIN reminder_guild rg on r.id = rg.reminder_id JOIN guilds g on rg.guild_id = g.id
-        WHERE u.discord_id = ? AND (g.discord_id = ? OR ? = -1) AND active = 1 ORDER BY timestamp ASC",
-        author_id, guild_id, guild_id
+        WHERE active = 1 AND (g.discord_id = ?2 OR ?2 = -1 OR ?3)
+            AND ((?4 AND r.creator_id = me.id)
+                OR (NOT ?4 AND EXISTS (SELECT 1 FROM reminder_user ru WHERE ru.reminder_id = r.id AND ru.user_id = me.id)
+                    AND (NOT ?5 OR r.creator_id IS NOT me.id)))
+            AND (?6 IS NULL OR instr(lower(message), lower(?6)) > 0)
+            AND (?7 IS NULL OR timestamp <= ?7)
+            AND (?8 IS NULL OR timestamp >= ?8)
+        ORDER BY timestamp ASC",
+        author_id, guild_id, everywhere, created, following, search, due_before, due_after
     )
     .fetch_all(&ctx.data().pool)
     .await?;
     if reminders.is_empty() {
+        if filters.is_filtered() {
+            return Err(
+                "Uh, none of your active reminders match that... M-maybe try a different search?"
+                    .into(),
+            );
+        }
         return Err("Uh, it looks like you don't have any active reminders right now... S-sorry about that!".into());
     }
+    match filters.sort {
+        ReminderSort::DueSoonest => {}
+        ReminderSort::DueLatest => reminders.reverse(),
+        ReminderSort::Newest => reminders.sort_by_key(|r| std::cmp::Reverse(r.created_at)),
+        ReminderSort::Oldest => reminders.sort_by_key(|r| r.created_at),
+    }
     let mut reminder_pages = Vec::<Vec<String>>::new();
     for (i, r) in reminders.iter().enumerate() {
         let reminder_string = format!("ID: {0} · <t:{1}:f> · `{2}` ([Context](https://hitori.discord.com/channels/{3}/{4}/{5}))", r.id, r.timestamp, r.message, url_guild_id(r.guild_id), r.channel_id, r.message_id);
@@ -44,11 +131,69 @@ pub async fn reminder_list(
         }
     }
 
-    paginate(
-        ctx,
-        &reminder_pages,
-        format!("Active reminders for {}", ctx.author().name),
-        start_page.unwrap_or_default(),
-    )
-    .await
+    let title = if filters.is_filtered() {
+        format!("Matching reminders for {}", ctx.author().name)
+    } else {
+        format!("Active reminders for {}", ctx.author().name)
+    };
+    paginate(ctx, &reminder_pages, title, start_page).await
+}
+
+/// Shows your list of reminders
+///
+/// /reminderlist <search> <due before> <due after> <owner> <scope> <sort> <start page>
+#[poise::command(slash_command, rename = "reminderlist")]
+#[allow(clippy::too_many_arguments)]
+pub async fn reminder_list_slash(
+    ctx: Context<'_>,
+    #[description = "Only show reminders mentioning this"] search: Option<String>,
+    #[description = "Only show reminders due before this time"] due_before: Option<String>,
+    #[description = "Only show reminders due after this time"] due_after: Option<String>,
+    #[description = "Whose reminders to show"] owner: Option<ReminderOwner>,
+    #[description = "Which servers to show reminders from"] scope: Option<ReminderScope>,
+    #[description = "How to order the reminders"] sort: Option<ReminderSort>,
+    #[description = "The page to start on"] start_page: Option<usize>,
+) -> Result<(), Error> {
+    let filters = ReminderFilters {
+        search,
+        due_before,
+        due_after,
+        owner: owner.unwrap_or_default(),
+        scope: scope.unwrap_or_default(),
+        sort: sort.unwrap_or_default(),
+    };
+    list_reminders(ctx, filters, start_page.unwrap_or_default()).await
+}
+
+/// Shows your list of reminders
+///
+/// h!reminderlist [page] [search] [--before <time>] [--after <time>] [--created | --following] [--everywhere] [--sort due|latest|newest|oldest]
+#[poise::command(rename = "reminderlist", prefix_command, aliases("reminders"))]
+pub async fn reminder_list_text(
+    ctx: Context<'_>, #[description = "The page to start on"] start_page: Option<usize>,
+    #[description = "Text to search for and filters"]
+    #[rest]
+    mut search: Option<String>,
+) -> Result<(), Error> {
+    let due_before = take_flag(&mut search, "before");
+    let due_after = take_flag(&mut search, "after");
+    let owner = if take_switch(&mut search, "created") {
+        ReminderOwner::Created
+    } else if take_switch(&mut search, "following") {
+        ReminderOwner::Following
+    } else {
+        ReminderOwner::All
+    };
+    let scope = if take_switch(&mut search, "everywhere") {
+        ReminderScope::Everywhere
+    } else {
+        ReminderScope::Here
+    };
+    let sort = match take_flag(&mut search, "sort") {
+        Some(sort) => ReminderSort::from_flag(&sort)?,
+        None => ReminderSort::default(),
+    };
+    let search = take_flag(&mut search, "search").or(search).filter(|s| !s.is_empty());
+    let filters = ReminderFilters { search, due_before, due_after, owner, scope, sort };
+    list_reminders(ctx, filters, start_page.unwrap_or_default()).await
 }
@@ -0,0 +1,42 @@
+use crate::commands::reminders::actions::update_visibility;
+use crate::commands::reminders::autocomplete::autocomplete_created_reminders;
+use crate::commands::reminders::visibility::{send_share_code, ReminderVisibility};
+use crate::{Context, Error, BOT_COLOR};
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor};
+use poise::CreateReply;
+
+/// Choose who can follow a reminder you created
+///
+/// h!remindervisibility <reminder ID> <private|server|shared>
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "remindervisibility",
+    aliases("rmvisibility", "reminderprivacy", "sharereminder"),
+    discard_spare_arguments
+)]
+pub async fn reminder_visibility(
+    ctx: Context<'_>,
+    #[description = "The reminder to change"]
+    #[autocomplete = "autocomplete_created_reminders"]
+    reminder_id: u32,
+    #[description = "Who can follow it"] visibility: ReminderVisibility,
+) -> Result<(), Error> {
+    let reminder_id = reminder_id as i64;
+    let share_code =
+        update_visibility(ctx.data(), reminder_id, ctx.author().id, visibility).await?;
+
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title(format!("Reminder #{reminder_id} updated."))
+        .description(format!(
+            "O-okay, now {}. Anyone who already follows it still will, though. I-I hope that's okay!",
+            visibility.explanation()
+        ));
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    if let Some(share_code) = share_code {
+        send_share_code(ctx, reminder_id, &share_code).await?;
+    }
+    Ok(())
+}
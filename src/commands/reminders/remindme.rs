@@ -1,88 +1,304 @@
+use crate::commands::reminders::recurrence::{anchor_recurrence, parse_recurrence};
 use crate::commands::reminders::util::{
-    cache_reminder, check_author_reminder_count, parse_timestamp,
+    check_author_reminder_count, check_user_reminder_count, parse_lead_times, parse_targets,
+    parse_timestamp, reschedule_reminders, set_warnings, take_flag, take_switch,
+    take_timestamp_words, validate_reminder_timestamp, MAX_NAGS, MAX_NAG_MINUTES, MAX_ROLE_MEMBERS,
+    MAX_TARGETS,
+};
+use crate::commands::reminders::visibility::{
+    get_default_visibility, send_share_code, set_visibility, ReminderVisibility,
 };
 use crate::commands::util::{
-    get_author_utc_offset, get_internal_channel_id, get_internal_guild_id, get_internal_user_id,
-    message_id_from_ctx, parse_utc_offset, referenced_from_ctx,
+    get_author_timezone, get_internal_channel_id, get_internal_guild_id, get_internal_role_id,
+    get_internal_user_id, message_id_from_ctx, parse_timezone, referenced_from_ctx,
 };
+use crate::events::reminders::{followers_value, remind_too_components, FOLLOWERS_FIELD};
 use crate::{Context, Error, BOT_COLOR};
-use chrono::Utc;
-use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter};
+use poise::serenity_prelude::{
+    ChannelId, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Message, Permissions, RoleId,
+    UserId,
+};
 use poise::CreateReply;
 use sqlx::query;
+use std::fmt::Write;
+
+#[derive(Default)]
+pub struct RemindmeOptions {
+    /// Time zone or UTC offset overriding the author's
+    pub offset: Option<String>,
+    /// Recurrence rule, see `parse_recurrence`
+    pub repeat: Option<String>,
+    /// Post the reminder in this channel instead of sending DMs
+    pub delivery_channel: Option<ChannelId>,
+    /// Other users and roles to remind, see `parse_targets`
+    pub targets: Option<String>,
+    /// Advance warnings for the author and the users in `targets`, see `parse_lead_times`.
+    /// Role members aren't followers, so they only get the reminder itself
+    pub before: Option<String>,
+    /// Minutes between repeats until every recipient says they did it
+    pub persistent: Option<i64>,
+    /// Who can follow the reminder, the author's default if not given
+    pub visibility: Option<ReminderVisibility>,
+    /// Message the reminder is about, used as the text and the context link
+    pub reference: Option<Message>,
+}
 
-const MAX_REMINDER_SECONDS: i64 = 34560000; // 400 days
+#[derive(poise::ChoiceParameter)]
+pub enum DeliveryMode {
+    #[name = "Direct message"]
+    Dm,
+    #[name = "This channel"]
+    Here,
+}
+
+async fn check_delivery_channel(ctx: Context<'_>, channel: ChannelId) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Err("Um, I can only post reminders in server channels... I-I'll send you a DM instead if you leave that out!".into());
+    };
+    let Some(guild_channel) = channel.to_channel(ctx).await?.guild() else {
+        return Err(
+            "Ah, you need to specify a text channel... I-I'm afraid only those are supported."
+                .into(),
+        );
+    };
+    if guild_channel.guild_id != guild_id {
+        return Err(
+            "Um, it seems this channel isn't from this guild... S-sorry, but I can't post there."
+                .into(),
+        );
+    }
+    let needed = Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES;
+    if !guild_channel
+        .permissions_for_user(ctx.serenity_context(), ctx.author().id)?
+        .contains(needed)
+    {
+        return Err("Um, y-you can't send messages in that channel yourself... so I-I can't post your reminder there either. S-sorry!".into());
+    }
+    let bot_id = ctx.cache().current_user().id;
+    if !guild_channel
+        .permissions_for_user(ctx.serenity_context(), bot_id)?
+        .contains(needed | Permissions::EMBED_LINKS)
+    {
+        return Err("Ah, um... I-I'm not allowed to send embeds in that channel... C-could someone give me permission first?".into());
+    }
+    Ok(())
+}
+
+/// Other users have to be in this server. Roles ping everyone in them, so unmentionable ones are
+/// only allowed for people who could ping them in the channel anyway
+async fn check_targets(
+    ctx: Context<'_>, users: &[UserId], roles: &[RoleId], ping_channel: ChannelId,
+) -> Result<(), Error> {
+    if users.is_empty() && roles.is_empty() {
+        return Ok(());
+    }
+    let Some(guild_id) = ctx.guild_id() else {
+        return Err("Um, I can only remind other people in servers... S-sorry!".into());
+    };
+    if users.len() + roles.len() > MAX_TARGETS {
+        return Err(format!("Ah, um, that's a lot of people... I-I can only remind up to {MAX_TARGETS} users or roles at once.").into());
+    }
+    for user in users {
+        if guild_id.member(ctx, *user).await.is_err() {
+            return Err(format!("Um, it seems <@{user}> isn't in this server... S-sorry, but I can't remind them here.").into());
+        }
+        if check_user_reminder_count(ctx.data(), *user).await.is_err() {
+            return Err(format!("Ah, um, <@{user}> already has too many active reminders... I-I'm afraid I can't add any more for them.").into());
+        }
+    }
+
+    let mut needs_mention_everyone = false;
+    if !roles.is_empty() {
+        let Some(guild) = ctx.guild() else {
+            return Err("Um, I-I can't see this server's roles right now... C-could you try again in a bit?".into());
+        };
+        for role in roles {
+            let Some(role) = guild.roles.get(role).filter(|r| r.id.get() != guild_id.get()) else {
+                return Err(format!("Um, it seems <@&{role}> isn't a role from this server... S-sorry, but I can't remind it here.").into());
+            };
+            needs_mention_everyone |= !role.mentionable;
+            let members =
+                guild.members.values().filter(|member| member.roles.contains(&role.id)).count();
+            if members > MAX_ROLE_MEMBERS {
+                return Err(format!("Ah, um, <@&{}> has a lot of people in it... I-I can only remind roles with up to {MAX_ROLE_MEMBERS} members.", role.id).into());
+            }
+        }
+    }
+    if needs_mention_everyone {
+        let Some(guild_channel) = ping_channel.to_channel(ctx).await?.guild() else {
+            return Err(
+                "Ah, you need to specify a text channel... I-I'm afraid only those are supported."
+                    .into(),
+            );
+        };
+        if !guild_channel
+            .permissions_for_user(ctx.serenity_context(), ctx.author().id)?
+            .contains(Permissions::MENTION_EVERYONE)
+        {
+            return Err("Um, y-you need permission to mention everyone to remind that role... so I-I can't do it for you. S-sorry!".into());
+        }
+    }
+    Ok(())
+}
 
 pub async fn remindme(
-    ctx: Context<'_>, timestamp: String, mut message: Option<String>, offset: Option<String>,
+    ctx: Context<'_>, timestamp: String, mut message: Option<String>, options: RemindmeOptions,
 ) -> Result<(), Error> {
-    let parsed_offset = if let Some(offset) = offset {
-        parse_utc_offset(ctx.data(), &offset)? as i64
+    let RemindmeOptions {
+        offset,
+        repeat,
+        delivery_channel,
+        targets,
+        before,
+        persistent,
+        visibility,
+        reference,
+    } = options;
+    if let Some(channel) = delivery_channel {
+        check_delivery_channel(ctx, channel).await?;
+    }
+    let (mut target_users, target_roles) = match &targets {
+        Some(targets) => parse_targets(targets)?,
+        None => (Vec::new(), Vec::new()),
+    };
+    target_users.retain(|user| *user != ctx.author().id);
+    check_targets(ctx, &target_users, &target_roles, delivery_channel.unwrap_or(ctx.channel_id()))
+        .await?;
+    let tz = if let Some(offset) = offset {
+        parse_timezone(ctx.data(), &offset)?
     } else {
-        get_author_utc_offset(&ctx).await?
+        get_author_timezone(&ctx).await?
     };
 
-    let unix_timestamp = parse_timestamp(ctx.data(), &timestamp, parsed_offset)?;
-    if unix_timestamp > Utc::now().timestamp() + MAX_REMINDER_SECONDS {
-        return Err("U-um... I'm really sorry, but... this reminder duration is... uh... too long! I-I might forget it, so... could we maybe shorten it? If that's okay with you...?".into());
+    let unix_timestamp = parse_timestamp(ctx.data(), &timestamp, tz)?;
+    validate_reminder_timestamp(unix_timestamp)?;
+    let lead_times = match &before {
+        Some(before) => parse_lead_times(&ctx.data().regex_cache, before)?,
+        None => Vec::new(),
     };
-    if unix_timestamp < Utc::now().timestamp() {
-        return Err("Ah! Um... the reminder... it has to be in the future! I-I can't, um... go back in time or anything... S-sorry about that!".into());
+    if persistent.is_some_and(|minutes| !(1..=MAX_NAG_MINUTES).contains(&minutes)) {
+        return Err(format!(
+            "Um, I-I can only keep reminding you every 1 to {MAX_NAG_MINUTES} minutes... S-sorry!"
+        )
+        .into());
     }
+    let repeat = match repeat {
+        Some(repeat) => {
+            let repeat = anchor_recurrence(&ctx.data().regex_cache, &repeat, unix_timestamp, tz)?;
+            parse_recurrence(&ctx.data().regex_cache, &repeat)?.validate(unix_timestamp, tz)?;
+            Some(repeat)
+        }
+        None => None,
+    };
+    let visibility = match visibility {
+        Some(visibility) => visibility,
+        None => get_default_visibility(ctx.data(), ctx.author().id).await?,
+    };
 
-    if let Some(reference) = referenced_from_ctx(ctx) {
+    let context_message_id = reference.as_ref().map(|reference| reference.id);
+    if let Some(reference) = reference.or_else(|| referenced_from_ctx(ctx)) {
         if message.is_none() && !reference.content.is_empty() {
             message = Some(reference.content);
         }
     }
     let message = message.unwrap_or("something".to_string());
 
-    let message_id = message_id_from_ctx(ctx).get() as i64;
+    let context_message_id = context_message_id.or(message_id_from_ctx(ctx));
+    let message_id = context_message_id.map_or(0, |id| id.get() as i64);
     let created_at = ctx.created_at().unix_timestamp();
     let i_user_id = get_internal_user_id(ctx.data(), ctx.author().id).await?;
     let i_channel_id = get_internal_channel_id(ctx.data(), ctx.channel_id()).await?;
     let i_guild_id = get_internal_guild_id(ctx, ctx.guild_id()).await?;
+    let i_delivery_channel_id = match delivery_channel {
+        Some(channel) => Some(get_internal_channel_id(ctx.data(), channel).await?),
+        None => None,
+    };
+    let mut i_target_ids = Vec::with_capacity(target_users.len());
+    for user in &target_users {
+        i_target_ids.push(get_internal_user_id(ctx.data(), *user).await?);
+    }
+    let mut i_role_ids = Vec::with_capacity(target_roles.len());
+    for role in &target_roles {
+        i_role_ids.push(get_internal_role_id(ctx.data(), *role).await?);
+    }
 
+    // Later occurrences stay in the zone the first one was given in
+    let (utc_offset, timezone) = tz.to_db();
+
+    // A reminder missing its follower, channel or guild rows can't be delivered, all or nothing
+    let mut tx = ctx.data().pool.begin().await?;
     let reminder_id = query!(
-        "INSERT INTO reminders (message, timestamp, created_at, message_id) VALUES (?, ?, ?, ?)",
+        "INSERT INTO reminders (message, timestamp, created_at, message_id, recurrence, creator_id, delivery_channel, nag_minutes, utc_offset, timezone) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         message,
         unix_timestamp,
         created_at,
-        message_id
+        message_id,
+        repeat,
+        i_user_id,
+        i_delivery_channel_id,
+        persistent,
+        utc_offset,
+        timezone
     )
-    .execute(&ctx.data().pool)
+    .execute(&mut *tx)
     .await?
     .last_insert_rowid();
+    let share_code = set_visibility(&mut tx, reminder_id, visibility).await?;
 
     query!(
         r"INSERT INTO reminder_user (reminder_id, user_id) VALUES (?, ?)",
         reminder_id,
         i_user_id
     )
-    .execute(&ctx.data().pool)
+    .execute(&mut *tx)
     .await?;
+    set_warnings(&mut tx, reminder_id, i_user_id, &lead_times).await?;
+    for i_target_id in &i_target_ids {
+        query!(
+            r"INSERT INTO reminder_user (reminder_id, user_id) VALUES (?, ?)",
+            reminder_id,
+            i_target_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        set_warnings(&mut tx, reminder_id, *i_target_id, &lead_times).await?;
+    }
+    for i_role_id in &i_role_ids {
+        query!(
+            r"INSERT INTO reminder_role (reminder_id, role_id) VALUES (?, ?)",
+            reminder_id,
+            i_role_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
     query!(
         r"INSERT INTO reminder_channel (reminder_id, channel_id) VALUES (?, ?)",
         reminder_id,
         i_channel_id
     )
-    .execute(&ctx.data().pool)
+    .execute(&mut *tx)
     .await?;
     query!(
         r"INSERT INTO reminder_guild (reminder_id, guild_id) VALUES (?, ?)",
         reminder_id,
         i_guild_id
     )
-    .execute(&ctx.data().pool)
+    .execute(&mut *tx)
     .await?;
+    tx.commit().await?;
 
-    cache_reminder(ctx.data(), unix_timestamp);
-    let tip = if ctx.guild().is_some() {
+    reschedule_reminders(ctx.data());
+    // Only reminders anyone in the guild can follow get the button, `follow` would refuse the rest
+    let followable = visibility == ReminderVisibility::Guild && ctx.guild().is_some();
+    let tip = if followable {
         format!(
-            "U-um, just a quick tip! You can use... um, \"{0}follow {1}\", a-and I'll also remind you about the same thing... if you want!",
+            "U-um, just a quick tip! You can press \"Remind me too\" or use... um, \"{0}follow {1}\", a-and I'll also remind you about the same thing... if you want!",
             ctx.prefix(),
             reminder_id
         )
+    } else if visibility == ReminderVisibility::Shared {
+        "U-um, I'll send you a share code, a-and anyone you give it to can follow this reminder too!".to_string()
     } else {
         format!(
             "U-uh, if you ever don't need the reminder anymore, you can just use \"{0}unfollow {1}\" to... um, remove it. I-it's totally fine if you change your mind!",
@@ -90,34 +306,115 @@ pub async fn remindme(
             reminder_id
         )
     };
-    let embed = CreateEmbed::new()
+    let mut description = format!(
+        "O-okay! I'll remind you in... um, <t:{unix_timestamp}:R>, at <t:{unix_timestamp}:F>, about... uh... {message}! I-I hope that's okay!"
+    );
+    if let Some(repeat) = repeat {
+        write!(description, " A-after that, I'll keep reminding you `{repeat}`.")?;
+    }
+    if let Some(channel) = delivery_channel {
+        write!(description, " I'll post it in <#{channel}> instead of sending DMs.")?;
+    }
+    if let Some(before) = before.filter(|_| !lead_times.is_empty()) {
+        let who = if target_users.is_empty() { "you" } else { "everyone" };
+        write!(description, " I'll also give {who} a heads-up `{before}` before.")?;
+    }
+    if let Some(minutes) = persistent {
+        write!(description, " Until you press \"I did it\", I'll keep reminding you every {minutes} minutes, up to {MAX_NAGS} more times.")?;
+    }
+    if !target_users.is_empty() || !target_roles.is_empty() {
+        description.push_str(" I'll also remind");
+        for user in &target_users {
+            write!(description, " <@{user}>")?;
+        }
+        for role in &target_roles {
+            write!(description, " <@&{role}>")?;
+        }
+        description.push('.');
+    }
+    let mut embed = CreateEmbed::new()
         .author(CreateEmbedAuthor::from(ctx.author().clone()))
         .color(BOT_COLOR)
         .title(format!("Reminder #{reminder_id} created."))
-        .description(format!(
-            "O-okay! I'll remind you in... um, <t:{unix_timestamp}:R>, at <t:{unix_timestamp}:F>, about... uh... {message}! I-I hope that's okay!"
-        ))
+        .description(description)
         .footer(CreateEmbedFooter::new(tip));
-    ctx.send(CreateReply::default().embed(embed)).await?;
+    let mut reply = CreateReply::default();
+    if followable {
+        // Recounted whenever the button is pressed, see `remind_too_components`
+        embed = embed.field(FOLLOWERS_FIELD, followers_value(1 + target_users.len()), true);
+        reply = reply.components(vec![remind_too_components(reminder_id)]);
+    }
+    let reply = ctx.send(reply.embed(embed)).await?;
+    if context_message_id.is_none() {
+        // Slash commands have no message of their own, so the confirmation becomes the context.
+        // The reminder already exists at this point, so a failure only costs the jump link
+        let patched = match reply.message().await {
+            Ok(message) => {
+                let message_id = message.id.get() as i64;
+                query!("UPDATE reminders SET message_id = ? WHERE id = ?", message_id, reminder_id)
+                    .execute(&ctx.data().pool)
+                    .await
+                    .map_err(Error::from)
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = patched {
+            tracing::warn!("{reminder_id} failed to store its confirmation message: {e}");
+        }
+    }
+    if let Some(share_code) = share_code {
+        send_share_code(ctx, reminder_id, &share_code).await?;
+    }
     Ok(())
 }
 
 /// Create a reminder
 ///
-/// /remindme <timestamp> <message> <utc offset>
+/// /remindme <timestamp> <message> <utc offset> <repeat> <deliver> <channel> <targets> <before> <persistent> <visibility>
 #[poise::command(slash_command, check = "check_author_reminder_count")]
 pub async fn remindme_slash(
     ctx: Context<'_>, #[description = "When you want to be reminded"] timestamp: String,
     #[description = "What you would like to be reminded of"] message: Option<String>,
-    #[description = "Override your default UTC offset"] offset: Option<String>,
+    #[description = "Override your default time zone or UTC offset"] offset: Option<String>,
+    #[description = "Repeat the reminder, e.g. \"every weekday at 09:00\" or \"1st of each month\""]
+    repeat: Option<String>,
+    #[description = "Where to deliver the reminder, DMs by default"] deliver: Option<DeliveryMode>,
+    #[description = "Post the reminder in this channel instead"]
+    #[channel_types("Text")]
+    channel: Option<ChannelId>,
+    #[description = "Other users or roles to remind as well, roles may need Mention Everyone"]
+    targets: Option<String>,
+    #[description = "Also warn you and the users you add this long before, e.g. 1h,10m"]
+    before: Option<String>,
+    #[description = "Keep reminding you every this many minutes until you say you did it"]
+    persistent: Option<u32>,
+    #[description = "Who else can follow this reminder"] visibility: Option<ReminderVisibility>,
 ) -> Result<(), Error> {
-    remindme(ctx, timestamp, message, offset).await?;
+    let delivery_channel = match (channel, deliver) {
+        (Some(channel), _) => Some(channel),
+        (None, Some(DeliveryMode::Here)) if ctx.guild_id().is_some() => Some(ctx.channel_id()),
+        (None, _) => None,
+    };
+    let options = RemindmeOptions {
+        offset,
+        repeat,
+        delivery_channel,
+        targets,
+        before,
+        persistent: persistent.map(i64::from),
+        visibility,
+        reference: None,
+    };
+    remindme(ctx, timestamp, message, options).await?;
     Ok(())
 }
 
 /// Create a reminder
 ///
-/// h!remindme <timestamp> <message>
+/// h!remindme <timestamp> <message> [--repeat <rule>] [--here] [--channel <#channel>] [--for <@users/roles>] [--before <1h,10m>] [--persistent <minutes>] [--private | --public | --shared]
+///
+/// --before warns you and the users in --for, role members only get the reminder itself
+/// --for roles need the Mention Everyone permission, unless anyone can mention them
 #[poise::command(
     rename = "remindme",
     prefix_command,
@@ -128,8 +425,52 @@ pub async fn remindme_text(
     ctx: Context<'_>, #[description = "When you want to be reminded"] timestamp: String,
     #[description = "What you would like to be reminded of"]
     #[rest]
-    message: Option<String>,
+    mut message: Option<String>,
 ) -> Result<(), Error> {
-    remindme(ctx, timestamp, message, None).await?;
+    let repeat = take_flag(&mut message, "repeat");
+    let targets = take_flag(&mut message, "for");
+    let before = take_flag(&mut message, "before");
+    let persistent = match take_flag(&mut message, "persistent") {
+        Some(minutes) => Some(minutes.parse::<i64>().map_err(|_| {
+            "Um, I-I don't understand that... C-could you tell me the minutes between reminders, like --persistent 15?"
+        })?),
+        None => None,
+    };
+    let visibility = if take_switch(&mut message, "private") {
+        Some(ReminderVisibility::Private)
+    } else if take_switch(&mut message, "public") {
+        Some(ReminderVisibility::Guild)
+    } else if take_switch(&mut message, "shared") {
+        Some(ReminderVisibility::Shared)
+    } else {
+        None
+    };
+    let delivery_channel = match take_flag(&mut message, "channel") {
+        Some(channel) => Some(
+            channel
+                .trim_start_matches("<#")
+                .trim_end_matches('>')
+                .parse::<u64>()
+                .ok()
+                .filter(|id| *id != 0)
+                .map(ChannelId::new)
+                .ok_or(
+                    "Uh, I-I couldn't find that channel... C-could you mention it like #general?",
+                )?,
+        ),
+        None => (take_switch(&mut message, "here") && ctx.guild_id().is_some())
+            .then(|| ctx.channel_id()),
+    };
+    let (timestamp, message) = take_timestamp_words(ctx.data(), timestamp, message);
+    let options = RemindmeOptions {
+        repeat,
+        delivery_channel,
+        targets,
+        before,
+        persistent,
+        visibility,
+        ..Default::default()
+    };
+    remindme(ctx, timestamp, message, options).await?;
     Ok(())
 }
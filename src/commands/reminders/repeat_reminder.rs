@@ -0,0 +1,40 @@
+use crate::commands::reminders::actions::repeat_past_reminder;
+use crate::commands::reminders::util::check_author_reminder_count;
+use crate::{Context, Error, BOT_COLOR};
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor, User};
+use poise::CreateReply;
+
+pub fn repeat_embed(
+    author: &User, new_reminder_id: i64, unix_timestamp: i64, message: &str,
+) -> CreateEmbed {
+    CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(author.clone()))
+        .color(BOT_COLOR)
+        .title(format!("Reminder #{new_reminder_id} created."))
+        .description(format!(
+            "O-okay! I'll remind you again in... um, <t:{unix_timestamp}:R>, at <t:{unix_timestamp}:F>, about... uh... {message}! I-I hope that's okay!"
+        ))
+}
+
+/// Remind you again about one of your past reminders
+///
+/// h!repeatreminder <reminder ID> <timestamp>
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "repeatreminder",
+    aliases("repeatrm", "remindagain", "again"),
+    check = "check_author_reminder_count"
+)]
+pub async fn repeat_reminder(
+    ctx: Context<'_>, #[description = "The past reminder to repeat"] reminder_id: u32,
+    #[description = "When you want to be reminded again"]
+    #[rest]
+    timestamp: String,
+) -> Result<(), Error> {
+    let (new_reminder_id, unix_timestamp, message) =
+        repeat_past_reminder(ctx.data(), reminder_id as i64, ctx.author().id, &timestamp).await?;
+    let embed = repeat_embed(ctx.author(), new_reminder_id, unix_timestamp, &message);
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
@@ -1,62 +1,63 @@
-use crate::commands::reminders::util::{
-    cache_reminder, get_next_reminder_ts, reminder_exists_and_active, user_ids_from_reminder_id,
-};
-use crate::commands::util::get_internal_user_id;
+use crate::commands::reminders::actions::{unfollow_reminder, unfollow_reminders};
+use crate::commands::reminders::autocomplete::autocomplete_followed_reminders;
+use crate::commands::reminders::util::parse_reminder_ids;
+use crate::util::confirm;
 use crate::{Context, Error, BOT_COLOR};
-use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor};
+use poise::serenity_prelude::{
+    CreateEmbed, CreateEmbedAuthor, CreateInteractionResponse, CreateInteractionResponseMessage,
+};
 use poise::CreateReply;
-use sqlx::query;
 
-/// Unfollow or remove a reminder
+/// Unfollow or remove one or more reminders
 ///
-/// h!unfollow <reminder ID>
-#[poise::command(
-    slash_command,
-    prefix_command,
-    aliases("reminderremove", "removerm", "forgor"),
-    discard_spare_arguments
-)]
+/// h!unfollow <reminder IDs, like 3 7 10-12>
+#[poise::command(slash_command, prefix_command, aliases("reminderremove", "removerm", "forgor"))]
 pub async fn unfollow(
-    ctx: Context<'_>, #[description = "The reminder to stop tracking"] reminder_id: u32,
+    ctx: Context<'_>,
+    #[description = "The reminders to stop tracking, like 3 7 10-12"]
+    #[autocomplete = "autocomplete_followed_reminders"]
+    #[rest]
+    reminder_ids: String,
 ) -> Result<(), Error> {
-    let reminder_id = reminder_id as i64;
-    if !reminder_exists_and_active(ctx.data(), reminder_id).await {
-        return Err("Uh, it seems the reminder doesn't exist or it's already expired... S-sorry, but I can't remove you from it.".into());
-        // TODO: maybe get a better one for this?
+    let reminder_ids = parse_reminder_ids(&reminder_ids)?;
+    if let [reminder_id] = reminder_ids[..] {
+        return unfollow_one(ctx, reminder_id).await;
     }
-    let user_ids = user_ids_from_reminder_id(ctx.data(), reminder_id).await?;
 
-    let user_id = ctx.author().id;
-    if !user_ids.contains(&user_id) {
-        return Err("Um, it looks like you're not following this reminder... S-sorry, but I can't remove you from it.".into());
-    }
+    let listed_ids = reminder_ids.iter().map(|id| format!("#{id}")).collect::<Vec<_>>().join(", ");
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title(format!("Unfollow {} reminders?", reminder_ids.len()))
+        .description(format!("Um, a-are you sure you want to stop following {listed_ids}? Reminders nobody else is tracking will be removed for good..."));
+    let Some(press) = confirm(ctx, embed).await? else {
+        return Ok(());
+    };
+    let result = unfollow_reminders(ctx.data(), &reminder_ids, ctx.author().id).await?;
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .description(result.summary());
+    press
+        .create_response(
+            ctx.serenity_context(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new().embed(embed).components(Vec::new()),
+            ),
+        )
+        .await?;
+    Ok(())
+}
 
+async fn unfollow_one(ctx: Context<'_>, reminder_id: i64) -> Result<(), Error> {
     let description: String;
     let ephemeral: bool;
-    let i_user_id = get_internal_user_id(ctx.data(), user_id).await?;
-    query!(
-        "DELETE FROM reminder_user WHERE reminder_id = ? AND user_id = ?",
-        reminder_id,
-        i_user_id
-    )
-    .execute(&ctx.data().pool)
-    .await?;
-    if user_ids.len() > 1 {
-        description = format!("O-okay, you'll no longer be notified for reminder #{reminder_id}. I-I hope that's alright!");
-        ephemeral = true;
-    } else {
-        query!("UPDATE reminders SET active = 0 WHERE id = ?", reminder_id)
-            .execute(&ctx.data().pool)
-            .await?;
-        {
-            let mut next_reminder = ctx.data().next_reminder.lock().unwrap();
-            *next_reminder = None; // Clear the next reminder, as it is unknown whether this is the reminder being removed or not
-        }
-        if let Some(reminder) = get_next_reminder_ts(&ctx.data().pool).await {
-            cache_reminder(ctx.data(), reminder); // Populate the cached reminder again
-        }
+    if unfollow_reminder(ctx.data(), reminder_id, ctx.author().id).await? {
         description = format!("Um, reminder #{reminder_id} has been removed. S-since you were the only one tracking it, it... um, no longer exists. I-I hope that's okay!");
         ephemeral = false;
+    } else {
+        description = format!("O-okay, you'll no longer be notified for reminder #{reminder_id}. I-I hope that's alright!");
+        ephemeral = true;
     }
 
     let embed = CreateEmbed::new()
@@ -1,13 +1,24 @@
-use crate::commands::util::{matches_to_vecint, multiply_by_position};
-use crate::{Context, Data, Error};
-use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
-use poise::serenity_prelude::UserId;
+use crate::commands::reminders::natural_time::{is_filler_word, parse_natural_time};
+use crate::commands::util::{
+    get_internal_user_id, matches_to_vecint, multiply_by_position, UserTimezone,
+};
+use crate::{Context, Data, Error, RegexCache};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use poise::serenity_prelude::{RoleId, UserId};
 use regex::Captures;
-use sqlx::{query, query_scalar, SqlitePool};
+use sqlx::{query, query_scalar, SqliteConnection, SqlitePool};
 use std::sync::Arc;
 
 const MAX_REMINDERS: i64 = 25;
-const DAY_IN_SECONDS: i64 = 86400;
+const MAX_REMINDER_SECONDS: i64 = 34560000; // 400 days
+const MAX_TIMESTAMP_WORDS: usize = 5;
+pub const MAX_TARGETS: usize = 10;
+pub const MAX_ROLE_MEMBERS: usize = 100; // every one of them might need a DM
+const MAX_BULK_REMINDERS: usize = 100;
+const MAX_LEAD_TIMES: usize = 5;
+const MAX_LEAD_SECONDS: i64 = 2592000; // 30 days
+pub const MAX_NAG_MINUTES: i64 = 1440;
+pub const MAX_NAGS: i64 = 10;
 
 fn match_to_int(captures: &Captures) -> Result<i32, Error> {
     let Some(capture) = captures.get(1) else {
@@ -19,11 +30,18 @@ fn match_to_int(captures: &Captures) -> Result<i32, Error> {
     Ok(parsed_amount)
 }
 
-pub fn date_to_timestamp(year: i32, month: u32, day: u32) -> Result<i64, Error> {
-    let Some(dt) = Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).earliest() else {
+pub fn datetime_to_timestamp(tz: UserTimezone, local: NaiveDateTime) -> Result<i64, Error> {
+    let Some(timestamp) = tz.local_to_timestamp(local) else {
         return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into());
     };
-    Ok(dt.timestamp())
+    Ok(timestamp)
+}
+
+pub fn date_to_timestamp(year: i32, month: u32, day: u32, tz: UserTimezone) -> Result<i64, Error> {
+    let Some(date) = NaiveDate::from_ymd_opt(year, month, day) else {
+        return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into());
+    };
+    datetime_to_timestamp(tz, date.and_time(NaiveTime::MIN))
 }
 
 pub fn parse_ymd(
@@ -69,7 +87,38 @@ pub fn parse_naivetime(data: &[Option<i32>], hour_index: usize) -> Result<NaiveT
     Ok(time)
 }
 
-pub fn parse_timestamp(data: &Arc<Data>, timestamp: &str, offset: i64) -> Result<i64, Error> {
+/// Tries the compact formats first, then falls back to english phrases like "tomorrow at 5pm"
+pub fn parse_timestamp(data: &Arc<Data>, timestamp: &str, tz: UserTimezone) -> Result<i64, Error> {
+    parse_compact_timestamp(data, timestamp, tz)
+        .or_else(|error| parse_natural_time(timestamp, Utc::now().timestamp(), tz).ok_or(error))
+}
+
+/// Prefix commands split arguments on spaces, so move as many words from the message into the
+/// timestamp as still make a valid time, e.g. "tomorrow" + "at 5pm call mom"
+pub fn take_timestamp_words(
+    data: &Arc<Data>, timestamp: String, message: Option<String>,
+) -> (String, Option<String>) {
+    let Some(message) = message else {
+        return (timestamp, None);
+    };
+    let words: Vec<&str> = message.split_whitespace().collect();
+    for taken in (1..=words.len().min(MAX_TIMESTAMP_WORDS)).rev() {
+        // "friday the meeting" shouldn't take "the", it parses but means nothing on its own
+        if is_filler_word(words[taken - 1]) {
+            continue;
+        }
+        let candidate = format!("{timestamp} {}", words[..taken].join(" "));
+        if parse_timestamp(data, &candidate, UserTimezone::from_db(0, None)).is_ok() {
+            let rest = words[taken..].join(" ");
+            return (candidate, (!rest.is_empty()).then_some(rest));
+        }
+    }
+    (timestamp, Some(message))
+}
+
+fn parse_compact_timestamp(
+    data: &Arc<Data>, timestamp: &str, tz: UserTimezone,
+) -> Result<i64, Error> {
     let rc = &data.regex_cache;
     match timestamp.split_whitespace().count() {
         1 => {
@@ -81,18 +130,23 @@ pub fn parse_timestamp(data: &Arc<Data>, timestamp: &str, offset: i64) -> Result
             } else if let Some(captures) = &rc.date_ymd.captures(timestamp) {
                 let Ok(int_matches) = matches_to_vecint(captures) else { return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into()) };
                 let (year, month, day) = parse_ymd(&int_matches, 0, 2)?;
-                return Ok(date_to_timestamp(year, month, day)? - offset * 60);
+                return date_to_timestamp(year, month, day, tz);
             } else if let Some(captures) = &rc.date_dmy.captures(timestamp) {
                 let Ok(int_matches) = matches_to_vecint(captures) else { return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into()) };
                 let (year, month, day) = parse_ymd(&int_matches, 2, 0)?;
-                return Ok(date_to_timestamp(year, month, day)? - offset * 60);
+                return date_to_timestamp(year, month, day, tz);
             } else if let Some(captures) = &rc.time.captures(timestamp) {
                 let Ok(int_matches) = matches_to_vecint(captures) else { return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into()) };
                 let time = parse_naivetime(&int_matches, 0)?;
-                let date = Utc::now().date_naive();
-                let timestamp = NaiveDateTime::new(date, time).and_utc().timestamp() - offset * 60;
+                let Some(now) = tz.timestamp_to_local(Utc::now().timestamp()) else {
+                    return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into());
+                };
+                let timestamp = datetime_to_timestamp(tz, NaiveDateTime::new(now.date(), time))?;
                 if timestamp < Utc::now().timestamp() {
-                    return Ok(timestamp + DAY_IN_SECONDS);
+                    let Some(tomorrow) = now.date().succ_opt() else {
+                        return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into());
+                    };
+                    return datetime_to_timestamp(tz, NaiveDateTime::new(tomorrow, time));
                 }
                 return Ok(timestamp);
             } else if let Some(captures) = &rc.relative_minutes.captures(timestamp) {
@@ -114,7 +168,7 @@ pub fn parse_timestamp(data: &Arc<Data>, timestamp: &str, offset: i64) -> Result
                     return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into());
                 };
                 let time = parse_naivetime(&int_matches, 3)?;
-                return Ok(NaiveDateTime::new(date, time).and_utc().timestamp() - offset * 60);
+                return datetime_to_timestamp(tz, NaiveDateTime::new(date, time));
             } else if let Some(captures) = &rc.datetime_dmy.captures(timestamp) {
                 let Ok(int_matches) = matches_to_vecint(captures) else { return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into()) };
                 let (year, month, day) = parse_ymd(&int_matches, 2, 0)?;
@@ -122,7 +176,7 @@ pub fn parse_timestamp(data: &Arc<Data>, timestamp: &str, offset: i64) -> Result
                     return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into());
                 };
                 let time = parse_naivetime(&int_matches, 3)?;
-                return Ok(NaiveDateTime::new(date, time).and_utc().timestamp() - offset * 60);
+                return datetime_to_timestamp(tz, NaiveDateTime::new(date, time));
             }
             Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into())
         }
@@ -130,24 +184,42 @@ pub fn parse_timestamp(data: &Arc<Data>, timestamp: &str, offset: i64) -> Result
     }
 }
 
-pub fn cache_reminder(data: &Arc<Data>, r: i64) {
-    let mut next_reminder = data.next_reminder.lock().unwrap();
-    if let Some(stored_reminder) = *next_reminder {
-        if r < stored_reminder {
-            *next_reminder = Some(r);
-        }
-    } else {
-        *next_reminder = Some(r);
+pub fn validate_reminder_timestamp(timestamp: i64) -> Result<(), Error> {
+    if timestamp > Utc::now().timestamp() + MAX_REMINDER_SECONDS {
+        return Err("U-um... I'm really sorry, but... this reminder duration is... uh... too long! I-I might forget it, so... could we maybe shorten it? If that's okay with you...?".into());
+    };
+    if timestamp < Utc::now().timestamp() {
+        return Err("Ah! Um... the reminder... it has to be in the future! I-I can't, um... go back in time or anything... S-sorry about that!".into());
     }
+    Ok(())
+}
+
+/// Wakes the reminder task so it can recalculate when the next reminder is due
+pub fn reschedule_reminders(data: &Arc<Data>) {
+    data.reminder_notify.notify_one();
 }
 
+/// Failed deliveries waiting for a retry, advance warnings and nags wake the reminder task up too
 pub async fn get_next_reminder_ts(pool: &SqlitePool) -> Option<i64> {
-    let next_reminder =
-        query!("SELECT timestamp FROM reminders WHERE active = 1 ORDER BY timestamp ASC LIMIT 1")
-            .fetch_one(pool)
-            .await
-            .ok();
-    next_reminder.map(|x| x.timestamp)
+    query_scalar!(
+        r#"SELECT MIN(timestamp) AS "timestamp?: i64" FROM (
+            SELECT MAX(timestamp, COALESCE(next_attempt_at, 0)) AS timestamp FROM reminders WHERE active = 1
+            UNION ALL
+            SELECT next_attempt_at AS timestamp FROM deliveries WHERE next_attempt_at IS NOT NULL
+            UNION ALL
+            SELECT r.timestamp - w.lead_seconds AS timestamp
+            FROM reminder_warnings w
+            JOIN reminders r ON r.id = w.reminder_id
+            JOIN reminder_user ru ON ru.reminder_id = w.reminder_id AND ru.user_id = w.user_id
+            WHERE r.active = 1 AND (w.warned_occurrence IS NULL OR w.warned_occurrence != r.timestamp)
+            UNION ALL
+            SELECT next_nag_at AS timestamp FROM nags WHERE next_nag_at IS NOT NULL
+        )"#
+    )
+    .fetch_one(pool)
+    .await
+    .ok()
+    .flatten()
 }
 
 pub async fn reminder_exists_and_active(data: &Arc<Data>, reminder_id: i64) -> bool {
@@ -184,6 +256,96 @@ pub async fn user_ids_from_reminder_id(
     Ok(reminder.into_iter().map(|x| UserId::new(x.discord_id as u64)).collect::<Vec<UserId>>())
 }
 
+pub async fn role_ids_from_reminder_id(
+    data: &Arc<Data>, reminder_id: i64,
+) -> Result<Vec<RoleId>, Error> {
+    let roles = query!(
+        r"SELECT discord_id
+        FROM roles ro
+        JOIN reminder_role rr ON rr.role_id = ro.id
+        WHERE rr.reminder_id = ?",
+        reminder_id
+    )
+    .fetch_all(&data.pool)
+    .await?;
+    Ok(roles.into_iter().map(|x| RoleId::new(x.discord_id as u64)).collect())
+}
+
+/// Unlike `user_ids_from_reminder_id`, this also works for reminders that were already delivered,
+/// and counts role members the reminder was delivered to
+pub async fn user_follows_reminder(
+    data: &Arc<Data>, reminder_id: i64, user: UserId,
+) -> Result<bool, Error> {
+    let user_id = user.get() as i64;
+    let follows = query_scalar!(
+        r"SELECT EXISTS(
+            SELECT 1 FROM reminder_user ru JOIN users u ON ru.user_id = u.id
+            WHERE ru.reminder_id = ?1 AND u.discord_id = ?2
+        ) OR EXISTS(
+            SELECT 1 FROM deliveries d JOIN users u ON d.user_id = u.id
+            WHERE d.reminder_id = ?1 AND u.discord_id = ?2
+        )",
+        reminder_id,
+        user_id
+    )
+    .fetch_one(&data.pool)
+    .await?;
+    Ok(follows != 0)
+}
+
+/// Creates a new reminder for a single user with the message and context of an existing one,
+/// it's private since nobody else asked for it
+pub async fn copy_reminder(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, timestamp: i64,
+) -> Result<i64, Error> {
+    let i_user_id = get_internal_user_id(data, user).await?;
+    let created_at = Utc::now().timestamp();
+    let mut tx = data.pool.begin().await?;
+    let result = query!(
+        r"INSERT INTO reminders (message, timestamp, created_at, message_id, creator_id, nag_minutes, visibility)
+        SELECT message, ?, ?, message_id, ?, nag_minutes, 'private' FROM reminders WHERE id = ?",
+        timestamp,
+        created_at,
+        i_user_id,
+        reminder_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(
+            "U-um... it looks like the reminder doesn't exist anymore... S-sorry about that!"
+                .into(),
+        );
+    }
+    let new_reminder_id = result.last_insert_rowid();
+
+    query!(
+        r"INSERT INTO reminder_user (reminder_id, user_id) VALUES (?, ?)",
+        new_reminder_id,
+        i_user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    query!(
+        r"INSERT INTO reminder_channel (reminder_id, channel_id)
+        SELECT ?, channel_id FROM reminder_channel WHERE reminder_id = ?",
+        new_reminder_id,
+        reminder_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    query!(
+        r"INSERT INTO reminder_guild (reminder_id, guild_id)
+        SELECT ?, guild_id FROM reminder_guild WHERE reminder_id = ?",
+        new_reminder_id,
+        reminder_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(new_reminder_id)
+}
+
 pub async fn guild_from_reminder_id(data: &Arc<Data>, reminder_id: i64) -> Result<i64, Error> {
     let reminder = query!(
         r"SELECT discord_id 
@@ -204,16 +366,20 @@ pub async fn guild_from_reminder_id(data: &Arc<Data>, reminder_id: i64) -> Resul
 }
 
 pub async fn check_author_reminder_count(ctx: Context<'_>) -> Result<bool, Error> {
-    let author_id = ctx.author().id.get() as i64;
+    check_user_reminder_count(ctx.data(), ctx.author().id).await
+}
+
+pub async fn check_user_reminder_count(data: &Arc<Data>, user: UserId) -> Result<bool, Error> {
+    let user_id = user.get() as i64;
     let reminder_count = query!(
         r"SELECT COUNT(*) AS count 
         FROM reminders r 
         JOIN reminder_user ru ON r.id = ru.reminder_id 
         JOIN users u on ru.user_id = u.id 
         WHERE u.discord_id = ? AND active = 1",
-        author_id
+        user_id
     )
-    .fetch_one(&ctx.data().pool)
+    .fetch_one(&data.pool)
     .await?
     .count;
     if reminder_count >= MAX_REMINDERS {
@@ -221,3 +387,262 @@ pub async fn check_author_reminder_count(ctx: Context<'_>) -> Result<bool, Error
     }
     Ok(true)
 }
+
+/// Finds where a standalone `--flag` starts, ignoring words that merely contain it
+fn find_flag(text: &str, marker: &str) -> Option<usize> {
+    let mut search_from = 0;
+    loop {
+        let index = search_from + text[search_from..].find(marker)?;
+        let end = index + marker.len();
+        if (index == 0 || text[..index].ends_with(char::is_whitespace))
+            && (end == text.len() || text[end..].starts_with(char::is_whitespace))
+        {
+            return Some(index);
+        }
+        search_from = end;
+    }
+}
+
+/// Removes `--flag value` from a prefix command's message, returning the value
+pub fn take_flag(message: &mut Option<String>, flag: &str) -> Option<String> {
+    let text = message.as_ref()?;
+    let marker = format!("--{flag}");
+    let start = find_flag(text, &marker)?;
+    let value_start = start + marker.len();
+    let value_end = text[value_start..].find(" --").map_or(text.len(), |i| value_start + i);
+    let value = text[value_start..value_end].trim().to_string();
+    let remaining = format!("{} {}", text[..start].trim_end(), text[value_end..].trim_start())
+        .trim()
+        .to_string();
+    *message = (!remaining.is_empty()).then_some(remaining);
+    Some(value)
+}
+
+/// Removes a `--flag` that takes no value, leaving the words after it in the message
+pub fn take_switch(message: &mut Option<String>, flag: &str) -> bool {
+    let Some(text) = message.as_ref() else {
+        return false;
+    };
+    let marker = format!("--{flag}");
+    let Some(start) = find_flag(text, &marker) else {
+        return false;
+    };
+    let end = start + marker.len();
+    let remaining =
+        format!("{} {}", text[..start].trim_end(), text[end..].trim_start()).trim().to_string();
+    *message = (!remaining.is_empty()).then_some(remaining);
+    true
+}
+
+/// Splits "<@123> <@&456>" into the mentioned users and roles
+pub fn parse_targets(targets: &str) -> Result<(Vec<UserId>, Vec<RoleId>), Error> {
+    let mut users = Vec::new();
+    let mut roles = Vec::new();
+    for mention in targets.split_whitespace() {
+        let Some(inner) = mention.strip_prefix("<@").and_then(|m| m.strip_suffix('>')) else {
+            return Err(format!(
+                "Um, I-I don't know who {mention} is... C-could you mention them like @someone?"
+            )
+            .into());
+        };
+        let (is_role, id) = match inner.strip_prefix('&') {
+            Some(id) => (true, id),
+            None => (false, inner.trim_start_matches('!')),
+        };
+        let Some(id) = id.parse::<u64>().ok().filter(|id| *id != 0) else {
+            return Err(format!(
+                "Um, I-I don't know who {mention} is... C-could you mention them like @someone?"
+            )
+            .into());
+        };
+        if is_role {
+            roles.push(RoleId::new(id));
+        } else {
+            users.push(UserId::new(id));
+        }
+    }
+    users.sort_unstable();
+    users.dedup();
+    roles.sort_unstable();
+    roles.dedup();
+    Ok((users, roles))
+}
+
+/// Reads reminder IDs like "3 7 12", "#3, #7" or ranges like "10-12"
+pub fn parse_reminder_ids(ids: &str) -> Result<Vec<i64>, Error> {
+    let mut reminder_ids = Vec::new();
+    for part in ids.split(|c: char| c.is_whitespace() || c == ',').filter(|p| !p.is_empty()) {
+        let (start, end) = part.split_once('-').unwrap_or((part, part));
+        let (Ok(start), Ok(end)) = (
+            start.trim_start_matches('#').parse::<i64>(),
+            end.trim_start_matches('#').parse::<i64>(),
+        ) else {
+            return Err(format!("Um, I-I don't understand \"{part}\"... C-could you give me reminder IDs like 3 7 or 10-12?").into());
+        };
+        if start > end {
+            return Err(
+                format!("Uh, th-that range is backwards... D-did you mean {end}-{start}?").into()
+            );
+        }
+        if end - start >= MAX_BULK_REMINDERS as i64 {
+            return Err("Ah, um, th-that's too many reminders at once... C-could you make the range smaller?".into());
+        }
+        reminder_ids.extend(start..=end);
+        if reminder_ids.len() > MAX_BULK_REMINDERS {
+            return Err("Ah, um, th-that's too many reminders at once... C-could you make the range smaller?".into());
+        }
+    }
+    if reminder_ids.is_empty() {
+        return Err(
+            "Um, y-you didn't tell me which reminders... C-could you give me their IDs?".into()
+        );
+    }
+    reminder_ids.sort_unstable();
+    reminder_ids.dedup();
+    Ok(reminder_ids)
+}
+
+/// Reads lead times for advance warnings like "1h,10m", longest first. "none" means no warnings
+pub fn parse_lead_times(regex_cache: &RegexCache, lead_times: &str) -> Result<Vec<i64>, Error> {
+    if lead_times.trim().eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    let second_conversions: [i32; 7] = [31557600, 2629800, 604800, 86400, 3600, 60, 1]; // year, month, week, day, hour, minute, second
+    let mut parsed = Vec::new();
+    for lead_time in
+        lead_times.split(|c: char| c.is_whitespace() || c == ',').filter(|l| !l.is_empty())
+    {
+        let Some(captures) = regex_cache.relative_time.captures(lead_time) else {
+            return Err(format!("Um, I-I don't understand \"{lead_time}\"... C-could you give me lead times like 1h,10m?").into());
+        };
+        let seconds =
+            multiply_by_position(&matches_to_vecint(&captures)?, &second_conversions)? as i64;
+        if seconds <= 0 || seconds > MAX_LEAD_SECONDS {
+            return Err(
+                "Ah, um, I-I can only warn you up to 30 days before a reminder... S-sorry!".into(),
+            );
+        }
+        parsed.push(seconds);
+    }
+    if parsed.is_empty() {
+        return Err("Um, y-you didn't tell me when to warn you... C-could you give me lead times like 1h,10m?".into());
+    }
+    parsed.sort_unstable_by(|a, b| b.cmp(a));
+    parsed.dedup();
+    if parsed.len() > MAX_LEAD_TIMES {
+        return Err(format!(
+            "Ah, um, th-that's a lot of warnings... I-I can only do {MAX_LEAD_TIMES} per reminder."
+        )
+        .into());
+    }
+    Ok(parsed)
+}
+
+/// Replaces the user's advance warnings for a reminder. Warnings that would already be late for
+/// the current occurrence count as sent, so they only apply from the next one
+pub async fn set_warnings(
+    conn: &mut SqliteConnection, reminder_id: i64, i_user_id: i64, lead_times: &[i64],
+) -> Result<(), Error> {
+    query!(
+        "DELETE FROM reminder_warnings WHERE reminder_id = ? AND user_id = ?",
+        reminder_id,
+        i_user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    let now = Utc::now().timestamp();
+    for lead_seconds in lead_times {
+        query!(
+            r"INSERT INTO reminder_warnings (reminder_id, user_id, lead_seconds, warned_occurrence)
+            SELECT id, ?, ?, CASE WHEN timestamp - ? <= ? THEN timestamp END FROM reminders WHERE id = ?",
+            i_user_id,
+            lead_seconds,
+            lead_seconds,
+            now,
+            reminder_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Acknowledges a persistent reminder for one user, every pending occurrence if `occurrence` is
+/// `None`. Returns whether there was anything left to acknowledge
+pub async fn stop_nagging(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, occurrence: Option<i64>,
+) -> Result<bool, Error> {
+    let user_id = user.get() as i64;
+    let now = Utc::now().timestamp();
+    let result = query!(
+        r"UPDATE nags SET next_nag_at = NULL, acknowledged_at = ?1
+        WHERE reminder_id = ?2 AND (?3 IS NULL OR occurrence = ?3) AND acknowledged_at IS NULL
+            AND user_id = (SELECT id FROM users WHERE discord_id = ?4)",
+        now,
+        reminder_id,
+        occurrence,
+        user_id
+    )
+    .execute(&data.pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminder_ids_accept_lists_and_ranges() {
+        assert_eq!(parse_reminder_ids("3 7").unwrap(), vec![3, 7]);
+        assert_eq!(parse_reminder_ids("#3,#7, 10-12").unwrap(), vec![3, 7, 10, 11, 12]);
+        assert_eq!(parse_reminder_ids("#10-#12").unwrap(), vec![10, 11, 12]);
+        assert_eq!(parse_reminder_ids("5 4-6 5").unwrap(), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn reminder_ids_reject_bad_input() {
+        assert!(parse_reminder_ids("").is_err());
+        assert!(parse_reminder_ids(" , ").is_err());
+        assert!(parse_reminder_ids("3 seven").is_err());
+        assert!(parse_reminder_ids("3-").is_err());
+        let backwards = parse_reminder_ids("12-10").unwrap_err().to_string();
+        assert!(backwards.contains("10-12"));
+    }
+
+    #[test]
+    fn reminder_ids_are_limited() {
+        assert_eq!(parse_reminder_ids("1-100").unwrap().len(), MAX_BULK_REMINDERS);
+        assert!(parse_reminder_ids("1-101").is_err());
+        assert!(parse_reminder_ids("1-60 61-101").is_err());
+        // Duplicates only count once in the end, but ranges can't be used to pile them up
+        assert!(parse_reminder_ids("1-60 1-60").is_err());
+    }
+
+    #[test]
+    fn lead_times_are_sorted_longest_first() {
+        let regex_cache = RegexCache::new();
+        assert_eq!(parse_lead_times(&regex_cache, "10m,1h").unwrap(), vec![3600, 600]);
+        assert_eq!(
+            parse_lead_times(&regex_cache, "1d 1h30m, 60m").unwrap(),
+            vec![86400, 5400, 3600]
+        );
+        assert_eq!(parse_lead_times(&regex_cache, "30d").unwrap(), vec![MAX_LEAD_SECONDS]);
+        assert!(parse_lead_times(&regex_cache, " None ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn lead_times_reject_bad_input() {
+        let regex_cache = RegexCache::new();
+        assert!(parse_lead_times(&regex_cache, "").is_err());
+        assert!(parse_lead_times(&regex_cache, "soon").is_err());
+        assert!(parse_lead_times(&regex_cache, "0m").is_err());
+        assert!(parse_lead_times(&regex_cache, "31d").is_err());
+        assert!(parse_lead_times(&regex_cache, "1m,2m,3m,4m,5m,6m").is_err());
+        // Duplicates are dropped before counting
+        assert_eq!(
+            parse_lead_times(&regex_cache, "1m,2m,3m,4m,5m,5m").unwrap().len(),
+            MAX_LEAD_TIMES
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{Context, Data, Error, BOT_COLOR};
+use poise::serenity_prelude::{CreateEmbed, CreateMessage, UserId};
+use poise::CreateReply;
+use sqlx::{query, query_scalar, SqliteConnection};
+use std::sync::Arc;
+
+#[derive(poise::ChoiceParameter, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReminderVisibility {
+    #[default]
+    #[name = "Private"]
+    Private,
+    #[name = "Server"]
+    Guild,
+    #[name = "Shared"]
+    Shared,
+}
+
+impl ReminderVisibility {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReminderVisibility::Private => "private",
+            ReminderVisibility::Guild => "guild",
+            ReminderVisibility::Shared => "shared",
+        }
+    }
+
+    /// Anything unknown is treated as private, that's the safe side
+    pub fn from_db(visibility: &str) -> Self {
+        match visibility {
+            "guild" => ReminderVisibility::Guild,
+            "shared" => ReminderVisibility::Shared,
+            _ => ReminderVisibility::Private,
+        }
+    }
+
+    /// "anyone in the server can follow it" and so on, for confirmations
+    pub fn explanation(self) -> &'static str {
+        match self {
+            ReminderVisibility::Private => "only you and the people you added can see it",
+            ReminderVisibility::Guild => "anyone in the server can follow it",
+            ReminderVisibility::Shared => "only people with the share code can follow it",
+        }
+    }
+}
+
+pub async fn get_reminder_visibility(
+    data: &Arc<Data>, reminder_id: i64,
+) -> Result<ReminderVisibility, Error> {
+    let visibility = query_scalar!("SELECT visibility FROM reminders WHERE id = ?", reminder_id)
+        .fetch_one(&data.pool)
+        .await?;
+    Ok(ReminderVisibility::from_db(&visibility))
+}
+
+pub async fn get_default_visibility(
+    data: &Arc<Data>, user: UserId,
+) -> Result<ReminderVisibility, Error> {
+    let user_id = user.get() as i64;
+    let visibility =
+        query_scalar!("SELECT default_visibility FROM users WHERE discord_id = ?", user_id)
+            .fetch_optional(&data.pool)
+            .await?;
+    Ok(visibility.map_or(ReminderVisibility::Private, |v| ReminderVisibility::from_db(&v)))
+}
+
+/// Changes who can follow a reminder. Shared reminders keep their code, anything else revokes it,
+/// so sharing again later hands out a new one. Returns the share code if there is one
+pub async fn set_visibility(
+    conn: &mut SqliteConnection, reminder_id: i64, visibility: ReminderVisibility,
+) -> Result<Option<String>, Error> {
+    let visibility = visibility.as_str();
+    query!(
+        r"UPDATE reminders SET visibility = ?1,
+            share_code = CASE WHEN ?1 = 'shared' THEN COALESCE(share_code, lower(hex(randomblob(5)))) END
+        WHERE id = ?2",
+        visibility,
+        reminder_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(query_scalar!("SELECT share_code FROM reminders WHERE id = ?", reminder_id)
+        .fetch_one(&mut *conn)
+        .await?)
+}
+
+pub async fn reminder_from_share_code(data: &Arc<Data>, code: &str) -> Result<Option<i64>, Error> {
+    let code = code.trim().to_lowercase();
+    Ok(query_scalar!(
+        "SELECT id FROM reminders WHERE share_code = ? AND visibility = 'shared'",
+        code
+    )
+    .fetch_optional(&data.pool)
+    .await?)
+}
+
+/// The code works like an invite, so it's only ever shown to the creator
+pub async fn send_share_code(ctx: Context<'_>, reminder_id: i64, code: &str) -> Result<(), Error> {
+    let embed = CreateEmbed::new()
+        .color(BOT_COLOR)
+        .title(format!("Share code for reminder #{reminder_id}"))
+        .description(format!(
+            "Um, anyone you give this code to can follow the reminder with `follow {code}`... s-so only share it with people you trust!"
+        ));
+    match ctx {
+        poise::Context::Application(_) => {
+            ctx.send(CreateReply::default().embed(embed).ephemeral(true)).await?;
+        }
+        // Prefix replies can't be hidden, so it goes to DMs instead
+        poise::Context::Prefix(_) => {
+            ctx.author()
+                .direct_message(ctx, CreateMessage::new().embed(embed))
+                .await
+                .map_err(|_| "Um, I-I couldn't DM you the share code... C-could you use the slash command, or open your DMs?")?;
+        }
+    }
+    Ok(())
+}
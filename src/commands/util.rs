@@ -1,13 +1,17 @@
 use crate::{Context, Data, Error};
-use poise::serenity_prelude::{ChannelId, GuildId, Message, MessageId, UserId};
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeDelta, TimeZone};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use poise::serenity_prelude::{ChannelId, GuildId, Message, MessageId, RoleId, UserId};
 use regex::Captures;
 use sqlx::{query, query_scalar};
+use std::fmt::{self, Display, Formatter};
 use std::sync::Arc;
 
-pub fn message_id_from_ctx(ctx: Context<'_>) -> MessageId {
+/// Interactions don't have a message to link to until they're responded to
+pub fn message_id_from_ctx(ctx: Context<'_>) -> Option<MessageId> {
     match ctx {
-        Context::Application(actx) => actx.interaction.id.get().into(),
-        Context::Prefix(pctx) => pctx.msg.id,
+        Context::Application(_actx) => None,
+        Context::Prefix(pctx) => Some(pctx.msg.id),
     }
 }
 
@@ -64,6 +68,29 @@ pub async fn get_internal_channel_id(data: &Arc<Data>, channel: ChannelId) -> Re
         .await?)
 }
 
+pub async fn ensure_role_in_db(data: &Arc<Data>, role: RoleId) -> Result<(), Error> {
+    let role_id = role.get() as i64;
+    if query_scalar!(r"SELECT COUNT(1) FROM roles WHERE (discord_id) = (?)", role_id)
+        .fetch_one(&data.pool)
+        .await?
+        .eq(&1)
+    {
+        return Ok(());
+    }
+    query!(r"INSERT OR IGNORE INTO roles (discord_id) VALUES (?)", role_id)
+        .execute(&data.pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_internal_role_id(data: &Arc<Data>, role: RoleId) -> Result<i64, Error> {
+    let role_id = role.get() as i64;
+    ensure_role_in_db(data, role).await?;
+    Ok(query_scalar!(r"SELECT id FROM roles WHERE discord_id = ?", role_id)
+        .fetch_one(&data.pool)
+        .await?)
+}
+
 pub async fn ensure_guild_in_db(ctx: Context<'_>, guild: Option<GuildId>) -> Result<(), Error> {
     let guild_id = force_guild_id(guild);
     if query_scalar!(r"SELECT COUNT(1) FROM guilds WHERE (discord_id) = (?)", guild_id)
@@ -103,6 +130,11 @@ pub fn force_guild_id(guild: Option<GuildId>) -> i64 {
     }
 }
 
+/// DMs all share the `-1` guild, so a reminder from one is never shared with anyone through it
+pub fn shares_guild(reminder_guild_id: i64, guild: Option<GuildId>) -> bool {
+    reminder_guild_id != -1 && reminder_guild_id == force_guild_id(guild)
+}
+
 pub fn matches_to_vecint(captures: &Captures) -> Result<Vec<Option<i32>>, Error> {
     let mut int_matches = Vec::new();
     for capture in captures.iter().skip(1) {
@@ -155,10 +187,156 @@ pub fn parse_utc_offset(data: &Arc<Data>, offset: &str) -> Result<i32, Error> {
     Ok(minutes)
 }
 
-pub async fn get_author_utc_offset(ctx: &Context<'_>) -> Result<i64, Error> {
-    ensure_user_in_db(ctx.data(), ctx.author().id).await?;
-    let author_id = ctx.author().id.get() as i64;
-    Ok(query_scalar!(r"SELECT utc_offset FROM users WHERE (discord_id) = (?)", author_id)
-        .fetch_one(&ctx.data().pool)
-        .await?)
+/// A user's time zone, either a named IANA zone that follows DST or a legacy fixed offset
+#[derive(Clone, Copy)]
+pub enum UserTimezone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl UserTimezone {
+    /// Named zones take priority, users who never set one keep using their offset in minutes
+    pub fn from_db(utc_offset: i64, timezone: Option<&str>) -> Self {
+        match timezone.and_then(find_timezone) {
+            Some(tz) => UserTimezone::Named(tz),
+            None => UserTimezone::Fixed(
+                FixedOffset::east_opt(utc_offset as i32 * 60)
+                    .unwrap_or(FixedOffset::east_opt(0).unwrap()),
+            ),
+        }
+    }
+
+    /// The other way around, for storing the zone a reminder was created in
+    pub fn to_db(self) -> (i64, Option<&'static str>) {
+        match self {
+            UserTimezone::Fixed(offset) => (offset.local_minus_utc() as i64 / 60, None),
+            UserTimezone::Named(tz) => (0, Some(tz.name())),
+        }
+    }
+
+    pub fn timestamp_to_local(self, timestamp: i64) -> Option<NaiveDateTime> {
+        let utc = DateTime::from_timestamp(timestamp, 0)?.naive_utc();
+        Some(match self {
+            UserTimezone::Fixed(offset) => offset.from_utc_datetime(&utc).naive_local(),
+            UserTimezone::Named(tz) => tz.from_utc_datetime(&utc).naive_local(),
+        })
+    }
+
+    /// Wall-clock times that are skipped by DST are moved forward by the size of the gap,
+    /// times that happen twice resolve to the first one
+    pub fn local_to_timestamp(self, local: NaiveDateTime) -> Option<i64> {
+        let tz = match self {
+            UserTimezone::Fixed(offset) => {
+                return offset.from_local_datetime(&local).single().map(|dt| dt.timestamp())
+            }
+            UserTimezone::Named(tz) => tz,
+        };
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt.timestamp()),
+            LocalResult::None => {
+                // Gaps never last longer than a day, so this is the offset from before the gap
+                let before_gap = tz.from_utc_datetime(&(local - TimeDelta::days(1))).offset().fix();
+                Some(local.and_utc().timestamp() - before_gap.local_minus_utc() as i64)
+            }
+        }
+    }
+}
+
+impl Display for UserTimezone {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UserTimezone::Fixed(offset) => write!(f, "UTC{offset}"),
+            UserTimezone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+/// Case-insensitive lookup of IANA zone names like "europe/amsterdam"
+pub fn find_timezone(name: &str) -> Option<Tz> {
+    TZ_VARIANTS.iter().find(|tz| tz.name().eq_ignore_ascii_case(name.trim())).copied()
+}
+
+/// Accepts either a zone name or a UTC offset
+pub fn parse_timezone(data: &Arc<Data>, timezone: &str) -> Result<UserTimezone, Error> {
+    if let Some(tz) = find_timezone(timezone) {
+        return Ok(UserTimezone::Named(tz));
+    }
+    let minutes = parse_utc_offset(data, timezone)?;
+    Ok(UserTimezone::from_db(minutes as i64, None))
+}
+
+pub async fn get_author_timezone(ctx: &Context<'_>) -> Result<UserTimezone, Error> {
+    get_user_timezone(ctx.data(), ctx.author().id).await
+}
+
+pub async fn get_user_timezone(data: &Arc<Data>, user: UserId) -> Result<UserTimezone, Error> {
+    ensure_user_in_db(data, user).await?;
+    let user_id = user.get() as i64;
+    let user = query!(r"SELECT utc_offset, timezone FROM users WHERE (discord_id) = (?)", user_id)
+        .fetch_one(&data.pool)
+        .await?;
+    Ok(UserTimezone::from_db(user.utc_offset, user.timezone.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(datetime: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn utc(datetime: &str) -> i64 {
+        local(datetime).and_utc().timestamp()
+    }
+
+    #[test]
+    fn dms_never_share_a_guild() {
+        let guild = GuildId::new(1234);
+        assert!(shares_guild(1234, Some(guild)));
+        assert!(!shares_guild(1234, None));
+        assert!(!shares_guild(5678, Some(guild)));
+        assert!(!shares_guild(-1, None));
+    }
+
+    #[test]
+    fn named_zone_follows_dst() {
+        let tz = UserTimezone::from_db(0, Some("europe/amsterdam"));
+        assert_eq!(tz.local_to_timestamp(local("2026-01-15 12:00")), Some(utc("2026-01-15 11:00")));
+        assert_eq!(tz.local_to_timestamp(local("2026-07-15 12:00")), Some(utc("2026-07-15 10:00")));
+    }
+
+    #[test]
+    fn skipped_time_moves_forward_by_the_gap() {
+        // Clocks jump from 02:00 to 03:00, so 02:30 is read as 03:30 summer time
+        let tz = UserTimezone::from_db(0, Some("Europe/Amsterdam"));
+        assert_eq!(tz.local_to_timestamp(local("2026-03-29 02:30")), Some(utc("2026-03-29 01:30")));
+        assert_eq!(tz.local_to_timestamp(local("2026-03-29 03:30")), Some(utc("2026-03-29 01:30")));
+        assert_eq!(tz.local_to_timestamp(local("2026-03-29 01:59")), Some(utc("2026-03-29 00:59")));
+    }
+
+    #[test]
+    fn repeated_time_resolves_to_the_first() {
+        // Clocks go back from 03:00 to 02:00, 02:30 summer time comes first
+        let tz = UserTimezone::from_db(0, Some("Europe/Amsterdam"));
+        assert_eq!(tz.local_to_timestamp(local("2026-10-25 02:30")), Some(utc("2026-10-25 00:30")));
+        assert_eq!(tz.local_to_timestamp(local("2026-10-25 03:30")), Some(utc("2026-10-25 02:30")));
+    }
+
+    #[test]
+    fn unknown_zones_fall_back_to_the_fixed_offset() {
+        let tz = UserTimezone::from_db(330, None);
+        assert_eq!(tz.local_to_timestamp(local("2026-03-29 02:30")), Some(utc("2026-03-28 21:00")));
+        let tz = UserTimezone::from_db(-300, Some("Not/AZone"));
+        assert_eq!(tz.to_string(), "UTC-05:00");
+        assert_eq!(tz.local_to_timestamp(local("2026-10-25 02:30")), Some(utc("2026-10-25 07:30")));
+        assert_eq!(tz.timestamp_to_local(utc("2026-10-25 07:30")), Some(local("2026-10-25 02:30")));
+    }
+
+    #[test]
+    fn zones_round_trip_through_the_db() {
+        for (offset, timezone) in [(330, None), (-300, None), (0, Some("Europe/Amsterdam"))] {
+            assert_eq!(UserTimezone::from_db(offset, timezone).to_db(), (offset, timezone));
+        }
+    }
 }
@@ -1,9 +1,15 @@
 mod set_utc_offset;
 mod set_fallback_channel;
+mod set_timezone;
+mod set_stale_reminders;
+mod set_default_visibility;
 
-pub fn commands() -> [crate::Command; 2] {
+pub fn commands() -> [crate::Command; 5] {
     [
         set_utc_offset::set_utc_offset(),
-        set_fallback_channel::set_fallback_channel()
+        set_fallback_channel::set_fallback_channel(),
+        set_timezone::set_timezone(),
+        set_stale_reminders::set_stale_reminders(),
+        set_default_visibility::set_default_visibility()
     ]
 }
@@ -0,0 +1,44 @@
+use crate::commands::reminders::visibility::ReminderVisibility;
+use crate::commands::util::ensure_user_in_db;
+use crate::{Context, Error, BOT_COLOR};
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor};
+use poise::CreateReply;
+use sqlx::query;
+
+/// Choose who can follow your new reminders, unless you say otherwise when creating them
+///
+/// Example: h!setvisibility private
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "setvisibility",
+    aliases("defaultvisibility", "setprivacy"),
+    discard_spare_arguments
+)]
+pub async fn set_default_visibility(
+    ctx: Context<'_>,
+    #[description = "Who can follow your new reminders"] visibility: ReminderVisibility,
+) -> Result<(), Error> {
+    let explanation = match visibility {
+        ReminderVisibility::Private => "nobody else can follow your new reminders",
+        ReminderVisibility::Guild => "anyone in the server can follow your new reminders",
+        ReminderVisibility::Shared => "I'll give you a share code for every new reminder",
+    };
+
+    ensure_user_in_db(ctx.data(), ctx.author().id).await?;
+    let author_id = ctx.author().id.get() as i64;
+    let visibility = visibility.as_str();
+    query!("UPDATE users SET default_visibility = ? WHERE discord_id = ?", visibility, author_id)
+        .execute(&ctx.data().pool)
+        .await?;
+
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title("Default visibility set!")
+        .description(format!(
+            "O-okay, from now on {explanation}, unless you tell me otherwise. I-I hope that works for you!"
+        ));
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
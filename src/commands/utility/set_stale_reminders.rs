@@ -0,0 +1,73 @@
+use crate::commands::util::{ensure_guild_in_db, force_guild_id};
+use crate::{Context, Error, BOT_COLOR};
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor};
+use poise::CreateReply;
+use sqlx::query;
+
+const DEFAULT_GRACE_MINUTES: u32 = 10; // same as the column default
+const MAX_GRACE_MINUTES: u32 = 1440;
+
+#[derive(poise::ChoiceParameter)]
+pub enum StaleReminderMode {
+    #[name = "Deliver"]
+    Deliver,
+    #[name = "Drop"]
+    Drop,
+    #[name = "Summarize"]
+    Summarize,
+}
+
+/// Choose what happens to reminders that came due while the bot was offline
+///
+/// Example: h!setstalereminders summarize 10
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "setstalereminders",
+    aliases("stalereminders", "missedreminders"),
+    discard_spare_arguments,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn set_stale_reminders(
+    ctx: Context<'_>,
+    #[description = "Deliver them late, drop them, or send one summary per user"]
+    mode: StaleReminderMode,
+    #[description = "How many minutes late a reminder can be before it counts as missed"]
+    grace_minutes: Option<u32>,
+) -> Result<(), Error> {
+    let grace_minutes = grace_minutes.unwrap_or(DEFAULT_GRACE_MINUTES);
+    if grace_minutes > MAX_GRACE_MINUTES {
+        return Err(format!("Um, th-that's a really long grace period... C-could you keep it under {MAX_GRACE_MINUTES} minutes?").into());
+    }
+    let (mode, explanation) = match mode {
+        StaleReminderMode::Deliver => ("deliver", "deliver them like normal, just late"),
+        StaleReminderMode::Drop => ("drop", "skip them quietly"),
+        StaleReminderMode::Summarize => {
+            ("summarize", "send everyone one summary of what they missed")
+        }
+    };
+
+    ensure_guild_in_db(ctx, ctx.guild_id()).await?;
+    let guild_id = force_guild_id(ctx.guild_id());
+    let grace = grace_minutes as i64;
+    query!(
+        r"UPDATE guilds SET stale_reminders = ?, stale_grace_minutes = ? WHERE discord_id = ?",
+        mode,
+        grace,
+        guild_id
+    )
+    .execute(&ctx.data().pool)
+    .await?;
+
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title("Missed reminders updated.")
+        .description(format!(
+            "Okay, if I-I'm ever away when reminders come due, I'll {explanation} once they're more than {grace_minutes} minutes late. I-I hope that works for you!"
+        ));
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
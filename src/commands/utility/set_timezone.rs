@@ -0,0 +1,69 @@
+use crate::commands::util::{ensure_user_in_db, find_timezone, UserTimezone};
+use crate::{Context, Error, BOT_COLOR};
+use chrono::{NaiveTime, Utc};
+use chrono_tz::TZ_VARIANTS;
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedAuthor};
+use poise::CreateReply;
+use sqlx::query;
+
+const MAX_SUGGESTIONS: usize = 25;
+
+#[allow(clippy::unused_async)]
+async fn autocomplete_timezone<'a>(
+    _ctx: Context<'_>, partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let partial = partial.to_lowercase().replace(' ', "_");
+    TZ_VARIANTS
+        .iter()
+        .map(|tz| tz.name())
+        .filter(move |name| name.to_lowercase().contains(&partial))
+        .take(MAX_SUGGESTIONS)
+        .map(str::to_string)
+}
+
+/// Set your time zone, this follows daylight saving time unlike a UTC offset
+///
+/// Example: h!settimezone Europe/Amsterdam
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "settimezone",
+    aliases("settz", "timezone", "tz"),
+    discard_spare_arguments
+)]
+pub async fn set_timezone(
+    ctx: Context<'_>,
+    #[description = "Time zone, like Europe/Amsterdam"]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: String,
+) -> Result<(), Error> {
+    let Some(tz) = find_timezone(&timezone) else {
+        return Err("Uh, I-I don't know that time zone... C-could you pick one like \"Europe/Amsterdam\" or \"America/New_York\"?".into());
+    };
+    let user_timezone = UserTimezone::Named(tz);
+    let local_afternoon =
+        Utc::now().date_naive().and_time(NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+    let Some(afternoon) = user_timezone.local_to_timestamp(local_afternoon) else {
+        return Err(
+            "Uh, it looks like the time zone is invalid... C-could you check it and try again?"
+                .into(),
+        );
+    };
+
+    ensure_user_in_db(ctx.data(), ctx.author().id).await?;
+    let author_id = ctx.author().id.get() as i64;
+    let tz_name = tz.name();
+    query!("UPDATE users SET timezone = ? WHERE discord_id = ?", tz_name, author_id)
+        .execute(&ctx.data().pool)
+        .await?;
+
+    let embed = CreateEmbed::new()
+        .author(CreateEmbedAuthor::from(ctx.author().clone()))
+        .color(BOT_COLOR)
+        .title("Time zone set!")
+        .description(format!(
+            "Um, just a heads-up: 12:00 in {user_timezone} is <t:{afternoon}:t> in your local time. I-I'll keep up with daylight saving time for you!"
+        ));
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
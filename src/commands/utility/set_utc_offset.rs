@@ -29,9 +29,14 @@ pub async fn set_utc_offset(
 
     ensure_user_in_db(ctx.data(), ctx.author().id).await?;
     let author_id = ctx.author().id.get() as i64;
-    query!("UPDATE users SET utc_offset = ? WHERE discord_id = ?", offset_minutes, author_id,)
-        .execute(&ctx.data().pool)
-        .await?;
+    // A fixed offset replaces any named time zone the user set before
+    query!(
+        "UPDATE users SET utc_offset = ?, timezone = NULL WHERE discord_id = ?",
+        offset_minutes,
+        author_id,
+    )
+    .execute(&ctx.data().pool)
+    .await?;
 
     let embed = CreateEmbed::new()
         .author(CreateEmbedAuthor::from(ctx.author().clone()))
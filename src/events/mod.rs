@@ -0,0 +1,52 @@
+use crate::{Data, Error, FrameworkContext};
+use poise::serenity_prelude::{
+    ChunkGuildFilter, Context, CreateInteractionResponse, CreateInteractionResponseMessage,
+    FullEvent, Interaction,
+};
+use reminders::{nag_button, reminder_button, reminder_modal, snooze_button, snooze_modal};
+use std::sync::Arc;
+
+pub mod reminders;
+
+/// Handles gateway events that aren't tied to a running command, like buttons on old messages
+pub async fn event_handler(
+    ctx: &Context, event: &FullEvent, _framework: FrameworkContext<'_>, data: &Arc<Data>,
+) -> Result<(), Error> {
+    let interaction = match event {
+        // Role reminders find their members in the cache, only small guilds come with all of them
+        FullEvent::GuildCreate { guild, .. } => {
+            ctx.shard.chunk_guild(guild.id, None, false, ChunkGuildFilter::None, None);
+            return Ok(());
+        }
+        FullEvent::InteractionCreate { interaction } => interaction,
+        _ => return Ok(()),
+    };
+    let result = match interaction {
+        Interaction::Component(press) => match press.data.custom_id.split_once(':') {
+            Some(("snooze", args)) => snooze_button(ctx, data, press, args).await,
+            Some(("reminder", args)) => reminder_button(ctx, data, press, args).await,
+            Some(("nag", args)) => nag_button(ctx, data, press, args).await,
+            _ => Ok(()), // Probably handled by a collector
+        },
+        Interaction::Modal(submit) => match submit.data.custom_id.split_once(':') {
+            Some(("snooze", args)) => snooze_modal(ctx, data, submit, args).await,
+            Some(("reminder", args)) => reminder_modal(ctx, data, submit, args).await,
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    };
+
+    // There's no command context to report errors to, so let the user know directly
+    if let Err(error) = result {
+        tracing::warn!("interaction failed: {error}");
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(error.to_string()).ephemeral(true),
+        );
+        let _ = match interaction {
+            Interaction::Component(press) => press.create_response(ctx, response).await,
+            Interaction::Modal(submit) => submit.create_response(ctx, response).await,
+            _ => Ok(()),
+        };
+    }
+    Ok(())
+}
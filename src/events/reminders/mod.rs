@@ -0,0 +1,10 @@
+pub use nag::{nag_button, nag_components};
+pub use reminder_buttons::{
+    followers_value, remind_too_components, reminder_button, reminder_info_components,
+    reminder_modal, FOLLOWERS_FIELD,
+};
+pub use snooze::{snooze_button, snooze_components, snooze_modal};
+
+mod nag;
+mod reminder_buttons;
+mod snooze;
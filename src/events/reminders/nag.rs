@@ -0,0 +1,42 @@
+use crate::commands::reminders::util::{stop_nagging, user_follows_reminder};
+use crate::{Data, Error};
+use poise::serenity_prelude::{
+    ButtonStyle, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use std::sync::Arc;
+
+/// The button persistent reminders keep coming back with until it's pressed, handled by
+/// `nag_button` even after a restart
+pub fn nag_components(reminder_id: i64, occurrence: i64) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(format!("nag:{reminder_id}:{occurrence}"))
+        .label("I did it")
+        .emoji('✅')
+        .style(ButtonStyle::Success)])
+}
+
+pub async fn nag_button(
+    ctx: &Context, data: &Arc<Data>, press: &ComponentInteraction, args: &str,
+) -> Result<(), Error> {
+    let Some((reminder_id, occurrence)) = args.split_once(':') else {
+        return Err("Um, I-I don't recognize this button... S-sorry!".into());
+    };
+    let reminder_id = reminder_id.parse::<i64>()?;
+    let occurrence = occurrence.parse::<i64>()?;
+
+    // Only the person pressing is acknowledged, everyone else keeps getting reminded
+    let content = if stop_nagging(data, reminder_id, press.user.id, Some(occurrence)).await? {
+        "G-great job! I'll stop bugging you about this one now."
+    } else if user_follows_reminder(data, reminder_id, press.user.id).await? {
+        "Um, y-you already told me you did it... I-I won't bug you again, promise!"
+    } else {
+        return Err(
+            "Um, th-this isn't your reminder... S-sorry, but I can't check it off for you.".into(),
+        );
+    };
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content(content).ephemeral(true),
+    );
+    press.create_response(ctx, response).await?;
+    Ok(())
+}
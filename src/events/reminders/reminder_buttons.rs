@@ -0,0 +1,222 @@
+use crate::commands::reminders::actions::{
+    delete_reminder, follow_reminder, reminder_creator, unfollow_reminder, update_reminder,
+    FollowVia,
+};
+use crate::commands::reminders::util::{reminder_exists_and_active, user_ids_from_reminder_id};
+use crate::{Data, Error, BOT_COLOR};
+use chrono::Utc;
+use poise::serenity_prelude::{
+    ActionRowComponent, ButtonStyle, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInputText, CreateInteractionResponse, CreateInteractionResponseFollowup,
+    CreateInteractionResponseMessage, CreateModal, EmbedField, InputTextStyle, ModalInteraction,
+};
+use sqlx::query_scalar;
+use std::sync::Arc;
+
+/// Buttons for `reminderinfo`, depending on how the viewer is related to the reminder
+pub fn reminder_info_components(
+    reminder_id: i64, can_follow: bool, following: bool, creator: bool,
+) -> Option<CreateActionRow> {
+    let mut buttons = Vec::new();
+    if can_follow && !following {
+        buttons.push(
+            CreateButton::new(format!("reminder:{reminder_id}:follow"))
+                .label("Follow")
+                .style(ButtonStyle::Primary),
+        );
+    }
+    if following {
+        buttons.push(
+            CreateButton::new(format!("reminder:{reminder_id}:unfollow"))
+                .label("Unfollow")
+                .style(ButtonStyle::Secondary),
+        );
+    }
+    if creator {
+        buttons.push(
+            CreateButton::new(format!("reminder:{reminder_id}:edit"))
+                .label("Edit")
+                .style(ButtonStyle::Secondary),
+        );
+        buttons.push(
+            CreateButton::new(format!("reminder:{reminder_id}:delete"))
+                .label("Delete")
+                .style(ButtonStyle::Danger),
+        );
+    }
+    (!buttons.is_empty()).then_some(CreateActionRow::Buttons(buttons))
+}
+
+pub const FOLLOWERS_FIELD: &str = "Followers";
+
+/// Following from anywhere else doesn't touch this message, so the count says when it was taken
+pub fn followers_value(follower_count: usize) -> String {
+    format!("{follower_count}, as of <t:{}:R>", Utc::now().timestamp())
+}
+
+/// The "Remind me too" button on `remindme`'s confirmation, pressing it again unfollows
+pub fn remind_too_components(reminder_id: i64) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(format!("reminder:{reminder_id}:toggle"))
+        .label("Remind me too")
+        .emoji('🔔')
+        .style(ButtonStyle::Primary)])
+}
+
+fn reply_embed(title: String, description: Option<String>) -> CreateEmbed {
+    let embed = CreateEmbed::new().color(BOT_COLOR).title(title);
+    match description {
+        Some(description) => embed.description(description),
+        None => embed,
+    }
+}
+
+fn reply(title: String, description: Option<String>) -> CreateInteractionResponse {
+    CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .embed(reply_embed(title, description))
+            .ephemeral(true),
+    )
+}
+
+/// Follows or unfollows from the button on the confirmation, then recounts its followers.
+/// The button goes away once the reminder is gone
+async fn toggle_follow(
+    ctx: &Context, data: &Arc<Data>, press: &ComponentInteraction, reminder_id: i64,
+) -> Result<(), Error> {
+    let user = press.user.id;
+    let following = user_ids_from_reminder_id(data, reminder_id).await?.contains(&user);
+    // Unfollowing as the creator would quietly delete the reminder for everyone
+    if following && reminder_creator(data, reminder_id).await? == Some(user) {
+        let response = reply(
+            "Um, this is your own reminder!".to_string(),
+            Some("Y-you're already getting it... I-if you don't want it anymore, you can use `unfollow` or the Delete button on `reminderinfo`.".to_string()),
+        );
+        press.create_response(ctx, response).await?;
+        return Ok(());
+    }
+    let (title, description) = if following {
+        if unfollow_reminder(data, reminder_id, user).await? {
+            (format!("Reminder #{reminder_id} removed."), Some("S-since you were the only one tracking it, it... um, no longer exists. I-I hope that's okay!".to_string()))
+        } else {
+            (format!("O-okay, you'll no longer be notified for reminder #{reminder_id}. I-I hope that's alright!"), None)
+        }
+    } else {
+        follow_reminder(data, reminder_id, user, FollowVia::Guild(press.guild_id), &[]).await?;
+        (format!("Um, y-you'll now be notified for reminder #{reminder_id}! I-I hope that works for you!"), None)
+    };
+
+    let follower_count = user_ids_from_reminder_id(data, reminder_id).await?.len();
+    let mut update = CreateInteractionResponseMessage::new();
+    if let Some(mut embed) = press.message.embeds.first().cloned() {
+        embed.fields.retain(|field| field.name != FOLLOWERS_FIELD);
+        embed.fields.push(EmbedField::new(FOLLOWERS_FIELD, followers_value(follower_count), true));
+        update = update.embed(CreateEmbed::from(embed));
+    }
+    if !reminder_exists_and_active(data, reminder_id).await {
+        update = update.components(Vec::new());
+    }
+    press.create_response(ctx, CreateInteractionResponse::UpdateMessage(update)).await?;
+    let followup = CreateInteractionResponseFollowup::new()
+        .embed(reply_embed(title, description))
+        .ephemeral(true);
+    press.create_followup(ctx, followup).await?;
+    Ok(())
+}
+
+/// Anyone who can see a `reminderinfo` or `remindme` message can press its buttons, so every
+/// action checks the presser's own relationship to the reminder again
+pub async fn reminder_button(
+    ctx: &Context, data: &Arc<Data>, press: &ComponentInteraction, args: &str,
+) -> Result<(), Error> {
+    let Some((reminder_id, action)) = args.split_once(':') else {
+        return Err("Um, I-I don't recognize this button... S-sorry!".into());
+    };
+    let reminder_id = reminder_id.parse::<i64>()?;
+    let user = press.user.id;
+
+    let response = match action {
+        "toggle" => return toggle_follow(ctx, data, press, reminder_id).await,
+        "follow" => {
+            follow_reminder(data, reminder_id, user, FollowVia::Guild(press.guild_id), &[]).await?;
+            reply(format!("Um, y-you'll now be notified for reminder #{reminder_id}! I-I hope that works for you!"), None)
+        }
+        "unfollow" => {
+            if unfollow_reminder(data, reminder_id, user).await? {
+                reply(format!("Reminder #{reminder_id} removed."), Some("S-since you were the only one tracking it, it... um, no longer exists. I-I hope that's okay!".to_string()))
+            } else {
+                reply(format!("O-okay, you'll no longer be notified for reminder #{reminder_id}. I-I hope that's alright!"), None)
+            }
+        }
+        "delete" => {
+            delete_reminder(data, reminder_id, user).await?;
+            reply(
+                format!("Reminder #{reminder_id} deleted."),
+                Some(
+                    "O-okay, I won't remind anyone about it anymore. I-I hope that's okay!"
+                        .to_string(),
+                ),
+            )
+        }
+        "edit" => {
+            if reminder_creator(data, reminder_id).await? != Some(user) {
+                return Err(
+                    "Ah, um, only the person who created this reminder can change it... S-sorry!"
+                        .into(),
+                );
+            }
+            let message = query_scalar!("SELECT message FROM reminders WHERE id = ?", reminder_id)
+                .fetch_one(&data.pool)
+                .await?;
+            let timestamp = CreateInputText::new(InputTextStyle::Short, "When?", "timestamp")
+                .placeholder("Leave empty to keep the current time")
+                .required(false);
+            let message = CreateInputText::new(InputTextStyle::Paragraph, "What?", "message")
+                .value(message)
+                .required(false);
+            let modal = CreateModal::new(format!("reminder:{reminder_id}"), "Edit reminder")
+                .components(vec![
+                    CreateActionRow::InputText(timestamp),
+                    CreateActionRow::InputText(message),
+                ]);
+            CreateInteractionResponse::Modal(modal)
+        }
+        _ => return Err("Um, I-I don't recognize this button... S-sorry!".into()),
+    };
+    press.create_response(ctx, response).await?;
+    Ok(())
+}
+
+pub async fn reminder_modal(
+    ctx: &Context, data: &Arc<Data>, submit: &ModalInteraction, args: &str,
+) -> Result<(), Error> {
+    let reminder_id = args.parse::<i64>()?;
+    let mut timestamp = None;
+    let mut message = None;
+    for component in submit.data.components.iter().flat_map(|row| row.components.iter()) {
+        if let ActionRowComponent::InputText(input) = component {
+            let value = input.value.clone().filter(|value| !value.trim().is_empty());
+            match input.custom_id.as_str() {
+                "timestamp" => timestamp = value,
+                "message" => message = value,
+                _ => {}
+            }
+        }
+    }
+    if timestamp.is_none() && message.is_none() {
+        return Err(
+            "Um, y-you didn't tell me what to change... C-could you give me a new time or message?"
+                .into(),
+        );
+    }
+
+    let (unix_timestamp, message) =
+        update_reminder(data, reminder_id, submit.user.id, timestamp.as_deref(), message).await?;
+    let response = reply(
+        format!("Reminder #{reminder_id} updated."),
+        Some(format!(
+            "O-okay! I'll remind everyone <t:{unix_timestamp}:R>, at <t:{unix_timestamp}:F>, about... uh... {message} instead! I-I hope that's okay!"
+        )),
+    );
+    submit.create_response(ctx, response).await?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crate::commands::reminders::util::{
+    check_user_reminder_count, copy_reminder, parse_timestamp, reschedule_reminders, stop_nagging,
+    user_follows_reminder, validate_reminder_timestamp,
+};
+use crate::commands::util::get_user_timezone;
+use crate::{Data, Error, BOT_COLOR};
+use chrono::Utc;
+use poise::serenity_prelude::{
+    ActionRowComponent, ButtonStyle, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInputText, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateModal, InputTextStyle, ModalInteraction, UserId,
+};
+use std::sync::Arc;
+
+const SNOOZE_OPTIONS: [(&str, i64); 3] =
+    [("Snooze 10m", 600), ("Snooze 1h", 3600), ("Tomorrow", 86400)];
+
+/// Buttons attached to delivered reminders, handled by `snooze_button` even after a restart
+pub fn snooze_components(reminder_id: i64) -> CreateActionRow {
+    let mut buttons: Vec<CreateButton> = SNOOZE_OPTIONS
+        .iter()
+        .map(|(label, seconds)| {
+            CreateButton::new(format!("snooze:{reminder_id}:{seconds}"))
+                .label(*label)
+                .style(ButtonStyle::Secondary)
+        })
+        .collect();
+    buttons.push(
+        CreateButton::new(format!("snooze:{reminder_id}:custom"))
+            .label("Custom...")
+            .style(ButtonStyle::Secondary),
+    );
+    CreateActionRow::Buttons(buttons)
+}
+
+async fn snooze(
+    data: &Arc<Data>, reminder_id: i64, user: UserId, timestamp: i64,
+) -> Result<CreateInteractionResponse, Error> {
+    if !user_follows_reminder(data, reminder_id, user).await? {
+        return Err(
+            "Um, th-this isn't your reminder... S-sorry, but I can't snooze it for you.".into()
+        );
+    }
+    validate_reminder_timestamp(timestamp)?;
+    check_user_reminder_count(data, user).await?;
+
+    let new_reminder_id = copy_reminder(data, reminder_id, user, timestamp).await?;
+    // The copy is persistent too, so it takes over the nagging
+    stop_nagging(data, reminder_id, user, None).await?;
+    reschedule_reminders(data);
+
+    let embed = CreateEmbed::new()
+        .color(BOT_COLOR)
+        .title(format!("Reminder #{new_reminder_id} created."))
+        .description(format!(
+            "O-okay! I'll remind you again <t:{timestamp}:R>, at <t:{timestamp}:F>. I-I hope that's okay!"
+        ));
+    Ok(CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed(embed).ephemeral(true),
+    ))
+}
+
+pub async fn snooze_button(
+    ctx: &Context, data: &Arc<Data>, press: &ComponentInteraction, args: &str,
+) -> Result<(), Error> {
+    let Some((reminder_id, duration)) = args.split_once(':') else {
+        return Err("Um, I-I don't recognize this button... S-sorry!".into());
+    };
+    let reminder_id = reminder_id.parse::<i64>()?;
+
+    if duration == "custom" {
+        let input = CreateInputText::new(InputTextStyle::Short, "When?", "timestamp")
+            .placeholder("10m, 2h30m, 18:00, 2025-03-01 14:00...");
+        let modal = CreateModal::new(format!("snooze:{reminder_id}"), "Snooze reminder")
+            .components(vec![CreateActionRow::InputText(input)]);
+        press.create_response(ctx, CreateInteractionResponse::Modal(modal)).await?;
+        return Ok(());
+    }
+
+    let timestamp = Utc::now().timestamp() + duration.parse::<i64>()?;
+    let response = snooze(data, reminder_id, press.user.id, timestamp).await?;
+    press.create_response(ctx, response).await?;
+    Ok(())
+}
+
+pub async fn snooze_modal(
+    ctx: &Context, data: &Arc<Data>, submit: &ModalInteraction, args: &str,
+) -> Result<(), Error> {
+    let reminder_id = args.parse::<i64>()?;
+    let Some(timestamp) =
+        submit.data.components.iter().flat_map(|row| row.components.iter()).find_map(|component| {
+            match component {
+                ActionRowComponent::InputText(input) => input.value.clone(),
+                _ => None,
+            }
+        })
+    else {
+        return Err("Ah, um... it looks like the timestamp is invalid... I-I don't really understand it. C-could you maybe check it again?".into());
+    };
+
+    let tz = get_user_timezone(data, submit.user.id).await?;
+    let timestamp = parse_timestamp(data, &timestamp, tz)?;
+    let response = snooze(data, reminder_id, submit.user.id, timestamp).await?;
+    submit.create_response(ctx, response).await?;
+    Ok(())
+}
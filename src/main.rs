@@ -12,22 +12,27 @@
 )]
 
 mod commands;
+mod events;
 mod tasks;
 mod util;
 
-use crate::tasks::task_handler;
+use crate::tasks::supervise_task_handler;
 use dotenvy::dotenv;
 use poise::{CreateReply, FrameworkError, serenity_prelude as serenity};
 use poise::serenity_prelude::{Color, CreateAllowedMentions};
 use regex::Regex;
-use sqlx::SqlitePool;
-use std::sync::{Arc, Mutex};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{query, query_scalar, SqlitePool};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Notify;
 
 const BOT_COLOR: Color = Color::new(0xfcaaf9);
 
 pub struct Data {
     regex_cache: RegexCache,
-    next_reminder: Mutex<Option<i64>>,
+    reminder_notify: Notify,
     pool: SqlitePool,
 } // User data, which is stored and accessible in all command invocations
 pub struct RegexCache {
@@ -50,34 +55,95 @@ pub struct RegexCache {
     /// \+ or - followed by hhmm
     utc_offset: Regex,
 }
+impl RegexCache {
+    fn new() -> Self {
+        Self {
+            relative_time: Regex::new(r"^(?:(\d+)[yY](?:[a-zA-Z]+)?)?(?:(\d+)(?:M|mo)(?:[a-zA-Z]+)?)?(?:(\d+)[wW](?:[a-zA-Z]+)?)?(?:(\d+)[dD](?:[a-zA-Z]+)?)?(?:(\d+)[hH](?:[a-zA-Z]+)?)?(?:(\d+)m(?:[a-zA-Z]+)?)?(?:(\d+)[sS](?:[a-zA-Z]+)?)?$").unwrap(),
+            datetime_ymd: Regex::new(r"^(2\d{3})[/\-.](1[012]|0?[1-9])[/\-.](3[01]|[12]\d|0?[1-9]) (2[0123]|1\d|0?\d)[:.]([12345]\d|0?\d)(?:[:.]([12345]\d|0?\d))?$").unwrap(),
+            datetime_dmy: Regex::new(r"^(3[01]|[12]\d|0?[1-9])[/\-.](1[012]|0?[1-9])(?:[/\-.](2\d{3}|\d{2}))? (2[0123]|1\d|0?\d)[:.]([12345]\d|0?\d)(?:[:.]([12345]\d|0?\d))?$").unwrap(),
+            date_ymd: Regex::new(r"^(2\d{3})[/\-.](1[012]|0?[1-9])[/\-.](3[01]|[12]\d|0?[1-9])$").unwrap(),
+            date_dmy: Regex::new(r"^(3[01]|[12]\d|0?[1-9])[/\-.](1[012]|0?[1-9])(?:[/\-.](2\d{3}|\d{2}))?$").unwrap(),
+            time: Regex::new(r"^(2[0123]|1\d|0?\d)[:.]([12345]\d|0?\d)(?:[:.]([12345]\d|0?\d))?$").unwrap(),
+            relative_minutes: Regex::new(r"^(\d{1,6})$").unwrap(),
+            unix_timestamp: Regex::new(r"^(?:<.:)?(\d{10,16})(?:(?::.)?>)?$").unwrap(),
+            utc_offset: Regex::new(r"(?i)^(?:UTC|GMT)?(-(?:1[0-2]|0?\d?)|\+?(?:1[0-4]|0?\d?)):?(00|30|45)?$").unwrap(),
+        }
+    }
+}
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Arc<Data>, Error>;
+type ApplicationContext<'a> = poise::ApplicationContext<'a, Arc<Data>, Error>;
 pub type FrameworkContext<'a> = poise::FrameworkContext<'a, Arc<Data>, Error>;
 pub type Command = poise::Command<Arc<Data>, Error>;
 
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Databases from before migrations were embedded had the init script applied by hand, so they
+/// have the tables but no record of it. Mark it as applied, otherwise sqlx would run it again
+async fn baseline_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let table_exists = |name: &'static str| {
+        query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+        )
+        .bind(name)
+        .fetch_one(pool)
+    };
+    if !table_exists("reminders").await? || table_exists("_sqlx_migrations").await? {
+        return Ok(());
+    }
+    let Some(init) = MIGRATOR.iter().find(|m| m.version == 20240716153618) else {
+        return Ok(());
+    };
+    tracing::info!("recording {} as already applied", init.version);
+    // Same table sqlx creates itself, so `run` picks up from here
+    query(
+        r"CREATE TABLE _sqlx_migrations (
+            version BIGINT PRIMARY KEY,
+            description TEXT NOT NULL,
+            installed_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            success BOOLEAN NOT NULL,
+            checksum BLOB NOT NULL,
+            execution_time BIGINT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await?;
+    query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (?, ?, TRUE, ?, 0)",
+    )
+    .bind(init.version)
+    .bind(&*init.description)
+    .bind(&*init.checksum)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
-    dotenv().expect(".env file not found");
+    // Deployments may set everything in the environment instead
+    dotenv().ok();
     tracing_subscriber::fmt::init();
-    let token = std::env::var("DISCORD_TOKEN").expect("missing DISCORD_TOKEN");
     let database_url = std::env::var("DATABASE_URL").expect("missing DATABASE_URL");
+    let connect_options = SqliteConnectOptions::from_str(&database_url)
+        .expect("invalid DATABASE_URL")
+        .create_if_missing(true);
+    let pool = SqlitePool::connect_with(connect_options).await.expect("failed to open database");
+    // Fail before connecting to discord, a half-migrated bot is worse than no bot
+    baseline_migrations(&pool).await.expect("failed to baseline database migrations");
+    MIGRATOR.run(&pool).await.expect("failed to run database migrations");
+    if std::env::args().any(|arg| arg == "--migrate-only") {
+        tracing::info!("database is up to date");
+        return;
+    }
+
+    let token = std::env::var("DISCORD_TOKEN").expect("missing DISCORD_TOKEN");
     let intents = serenity::GatewayIntents::non_privileged()
         | serenity::GatewayIntents::MESSAGE_CONTENT
         | serenity::GatewayIntents::GUILD_MEMBERS;
 
-    let regex_cache = RegexCache {
-        relative_time: Regex::new(r"^(?:(\d+)[yY](?:[a-zA-Z]+)?)?(?:(\d+)(?:M|mo)(?:[a-zA-Z]+)?)?(?:(\d+)[wW](?:[a-zA-Z]+)?)?(?:(\d+)[dD](?:[a-zA-Z]+)?)?(?:(\d+)[hH](?:[a-zA-Z]+)?)?(?:(\d+)m(?:[a-zA-Z]+)?)?(?:(\d+)[sS](?:[a-zA-Z]+)?)?$").unwrap(),
-        datetime_ymd: Regex::new(r"^(2\d{3})[/\-.](1[012]|0?[1-9])[/\-.](3[01]|[12]\d|0?[1-9]) (2[0123]|1\d|0?\d)[:.]([12345]\d|0?\d)(?:[:.]([12345]\d|0?\d))?$").unwrap(),
-        datetime_dmy: Regex::new(r"^(3[01]|[12]\d|0?[1-9])[/\-.](1[012]|0?[1-9])(?:[/\-.](2\d{3}|\d{2}))? (2[0123]|1\d|0?\d)[:.]([12345]\d|0?\d)(?:[:.]([12345]\d|0?\d))?$").unwrap(),
-        date_ymd: Regex::new(r"^(2\d{3})[/\-.](1[012]|0?[1-9])[/\-.](3[01]|[12]\d|0?[1-9])$").unwrap(),
-        date_dmy: Regex::new(r"^(3[01]|[12]\d|0?[1-9])[/\-.](1[012]|0?[1-9])(?:[/\-.](2\d{3}|\d{2}))?$").unwrap(),
-        time: Regex::new(r"^(2[0123]|1\d|0?\d)[:.]([12345]\d|0?\d)(?:[:.]([12345]\d|0?\d))?$").unwrap(),
-        relative_minutes: Regex::new(r"^(\d{1,6})$").unwrap(),
-        unix_timestamp: Regex::new(r"^(?:<.:)?(\d{10,16})(?:(?::.)?>)?$").unwrap(),
-        utc_offset: Regex::new(r"(?i)^(?:UTC|GMT)?(-(?:1[0-2]|0?\d?)|\+?(?:1[0-4]|0?\d?)):?(00|30|45)?$").unwrap(),
-    };
-    let pool = SqlitePool::connect(&database_url).await.unwrap();
-    let data = Arc::new(Data { regex_cache, next_reminder: Mutex::new(None), pool });
+    let regex_cache = RegexCache::new();
+    let data = Arc::new(Data { regex_cache, reminder_notify: Notify::new(), pool });
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
@@ -87,6 +153,9 @@ async fn main() {
             },
             commands: commands::commands(),
             on_error: |error| Box::pin(on_error(error)),
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(events::event_handler(ctx, event, framework, data))
+            },
             ..Default::default()
         })
         .setup(|ctx, _ready, framework| {
@@ -94,7 +163,7 @@ async fn main() {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
                 let ctx_clone = ctx.clone();
                 let data_clone = data.clone();
-                tokio::spawn(async move { task_handler(ctx_clone, data_clone).await });
+                tokio::spawn(supervise_task_handler(ctx_clone, data_clone));
                 Ok(data)
             })
         })
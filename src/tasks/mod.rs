@@ -1,21 +1,55 @@
-use crate::commands::reminders::util::{cache_reminder, get_next_reminder_ts};
+use crate::commands::reminders::util::get_next_reminder_ts;
 use crate::{Data, Error};
+use chrono::Utc;
 use poise::serenity_prelude::Context;
-use reminders::check_reminders;
+use reminders::{catch_up_reminders, check_reminders};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::time::interval;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
 
 mod reminders;
 
-pub async fn task_handler(ctx: Context, data: Arc<Data>) -> Result<(), Error> {
-    let mut reminder_interval = interval(Duration::from_secs(5));
-    if let Some(reminder) = get_next_reminder_ts(&data.pool).await {
-        cache_reminder(&data, reminder);
-    }
+const MIN_SLEEP_SECONDS: i64 = 1;
+const MAX_SLEEP_SECONDS: i64 = 3600; // wake up every now and then in case the clock jumped
+const MIN_RESTART_SECONDS: u64 = 1;
+const MAX_RESTART_SECONDS: u64 = 300;
+const HEALTHY_RUN_SECONDS: u64 = 600; // a task that ran this long starts over with a short backoff
+
+/// Restarts the reminder loop with exponential backoff whenever it panics or returns
+pub async fn supervise_task_handler(ctx: Context, data: Arc<Data>) {
+    let mut backoff = MIN_RESTART_SECONDS;
     loop {
-        reminder_interval.tick().await;
+        let started = Instant::now();
+        match tokio::spawn(task_handler(ctx.clone(), data.clone())).await {
+            Ok(Ok(())) => tracing::error!("reminder task stopped"),
+            Ok(Err(e)) => tracing::error!("reminder task failed: {e}"),
+            Err(e) => tracing::error!("reminder task panicked: {e}"),
+        }
+        if started.elapsed() >= Duration::from_secs(HEALTHY_RUN_SECONDS) {
+            backoff = MIN_RESTART_SECONDS;
+        }
+        tracing::info!("restarting reminder task in {backoff}s");
+        sleep(Duration::from_secs(backoff)).await;
+        backoff = (backoff * 2).min(MAX_RESTART_SECONDS);
+    }
+}
 
+pub async fn task_handler(ctx: Context, data: Arc<Data>) -> Result<(), Error> {
+    // Anything that came due while we were offline or restarting
+    catch_up_reminders(&ctx, &data).await;
+    loop {
         check_reminders(&ctx, &data).await;
+
+        // Sleep until the next reminder is due, or until a command changes the schedule
+        let sleep_seconds = match get_next_reminder_ts(&data.pool).await {
+            Some(timestamp) => {
+                (timestamp - Utc::now().timestamp()).clamp(MIN_SLEEP_SECONDS, MAX_SLEEP_SECONDS)
+            }
+            None => MAX_SLEEP_SECONDS,
+        };
+        tokio::select! {
+            () = sleep(Duration::from_secs(sleep_seconds as u64)) => {}
+            () = data.reminder_notify.notified() => {}
+        }
     }
 }
@@ -0,0 +1,227 @@
+use crate::commands::reminders::util::{role_ids_from_reminder_id, user_ids_from_reminder_id};
+use crate::tasks::reminders::check_reminders::{
+    fetch_reminder, finish_reminder, format_lateness, record_reminded, reminder_embed,
+    role_member_ids, DueReminder,
+};
+use crate::tasks::reminders::deliveries::{record_delivery, DeliveryKind, DeliveryOutcome};
+use crate::util::url_guild_id;
+use crate::{Data, Error};
+use chrono::Utc;
+use poise::serenity_prelude::{ChannelId, Context, CreateAllowedMentions, CreateMessage, UserId};
+use sqlx::query;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+const MAX_DIGEST_REMINDERS: usize = 15;
+const MAX_DIGEST_MESSAGE_CHARS: usize = 100;
+
+/// Handles reminders that came due while the bot was offline, before the normal loop gets to
+/// them. Guilds that want stale reminders delivered as usual are left to `check_reminders`,
+/// which mentions how late they are. Channel reminders are collected into one post per channel
+pub async fn catch_up_reminders(ctx: &Context, data: &Arc<Data>) {
+    let now = Utc::now().timestamp();
+    let stale_reminders = match query!(
+        r"SELECT r.id, g.stale_reminders AS mode
+        FROM reminders r
+        JOIN reminder_guild rg ON r.id = rg.reminder_id JOIN guilds g ON rg.guild_id = g.id
+        WHERE active = 1 AND timestamp <= ? - g.stale_grace_minutes * 60
+            AND g.stale_reminders != 'deliver'
+        ORDER BY timestamp ASC",
+        now
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(stale_reminders) => stale_reminders,
+        Err(e) => {
+            tracing::warn!("failed to fetch stale reminders: {e}");
+            return;
+        }
+    };
+    if stale_reminders.is_empty() {
+        return;
+    }
+    tracing::info!("catching up on {} reminders missed while offline", stale_reminders.len());
+
+    let mut reminders = Vec::new();
+    let mut digests = HashMap::<UserId, Vec<usize>>::new();
+    let mut channel_digests = HashMap::<i64, Vec<usize>>::new();
+    for stale in stale_reminders {
+        let r = match fetch_reminder(data, stale.id).await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::warn!("{} failed to catch up: {e}", stale.id);
+                continue;
+            }
+        };
+        match (stale.mode.as_str(), r.delivery_channel) {
+            ("drop", _) => match stale_recipients(ctx, data, &r).await {
+                Ok(user_ids) => {
+                    for user_id in user_ids {
+                        let (kind, outcome) = (DeliveryKind::Reminder, DeliveryOutcome::Dropped);
+                        record_delivery(data, kind, r.id, r.timestamp, user_id, 1, &outcome).await;
+                    }
+                }
+                Err(e) => tracing::warn!("{} failed to catch up: {e}", r.id),
+            },
+            (_, Some(channel)) => channel_digests.entry(channel).or_default().push(reminders.len()),
+            (_, None) => match stale_recipients(ctx, data, &r).await {
+                Ok(user_ids) => {
+                    for user_id in user_ids {
+                        digests.entry(user_id).or_default().push(reminders.len());
+                    }
+                }
+                Err(e) => tracing::warn!("{} failed to catch up: {e}", r.id),
+            },
+        }
+        reminders.push(r);
+    }
+
+    for (channel, indices) in channel_digests {
+        let channel_reminders: Vec<&DueReminder> = indices.iter().map(|&i| &reminders[i]).collect();
+        let channel = ChannelId::new(channel as u64);
+        if send_channel_digest(ctx, data, channel, &channel_reminders, now).await {
+            continue;
+        }
+        // Same as a normal delivery, a channel that can't be posted in falls back to DMs
+        for i in indices {
+            match stale_recipients(ctx, data, &reminders[i]).await {
+                Ok(user_ids) => {
+                    for user_id in user_ids {
+                        digests.entry(user_id).or_default().push(i);
+                    }
+                }
+                Err(e) => tracing::warn!("{} failed to catch up: {e}", reminders[i].id),
+            }
+        }
+    }
+    for (user_id, indices) in digests {
+        let user_reminders: Vec<&DueReminder> = indices.iter().map(|&i| &reminders[i]).collect();
+        send_digest(ctx, data, user_id, &user_reminders, now).await;
+    }
+    // Only now, so a crash in between catches them up again on the next start
+    for r in &reminders {
+        if let Err(e) = finish_reminder(data, r).await {
+            tracing::warn!("{} failed to finish after catching up: {e}", r.id);
+        }
+    }
+}
+
+/// Everyone who would have been reminded, role members are looked up like for a normal delivery
+async fn stale_recipients(
+    ctx: &Context, data: &Arc<Data>, r: &DueReminder,
+) -> Result<Vec<UserId>, Error> {
+    let mut user_ids = user_ids_from_reminder_id(data, r.id).await?;
+    let role_ids = role_ids_from_reminder_id(data, r.id).await?;
+    if !role_ids.is_empty() {
+        for member_id in role_member_ids(ctx, r.guild_id, &role_ids) {
+            if !user_ids.contains(&member_id) {
+                user_ids.push(member_id);
+            }
+        }
+    }
+    Ok(user_ids)
+}
+
+fn digest_description(reminders: &[&DueReminder], now: i64) -> String {
+    let mut description = String::from(
+        "Um, h-hey... I-I was away for a bit, and these came up while I was gone. S-sorry for being late!\n",
+    );
+    for r in reminders.iter().take(MAX_DIGEST_REMINDERS) {
+        let message: String = r.message.chars().take(MAX_DIGEST_MESSAGE_CHARS).collect();
+        write!(
+            description,
+            "\n**#{0}** · {1} · due <t:{2}:f>, {3} ago · [Context](https://hitori.discord.com/channels/{4}/{5}/{6})",
+            r.id,
+            message,
+            r.timestamp,
+            format_lateness(now - r.timestamp),
+            url_guild_id(r.guild_id),
+            r.channel_id,
+            r.message_id
+        )
+        .unwrap();
+    }
+    if reminders.len() > MAX_DIGEST_REMINDERS {
+        write!(
+            description,
+            "\n...and {} more, y-you can find them in your reminder history.",
+            reminders.len() - MAX_DIGEST_REMINDERS
+        )
+        .unwrap();
+    }
+    description
+}
+
+/// One post with every reminder that was meant for the channel, pinging everyone they were for.
+/// Returns false if it couldn't be posted
+async fn send_channel_digest(
+    ctx: &Context, data: &Arc<Data>, channel: ChannelId, reminders: &[&DueReminder], now: i64,
+) -> bool {
+    let mut followers = Vec::new();
+    let mut ping_users = Vec::new();
+    let mut ping_roles = Vec::new();
+    for r in reminders {
+        let user_ids = user_ids_from_reminder_id(data, r.id).await.unwrap_or_else(|e| {
+            tracing::warn!("{} failed to fetch followers for the digest: {e}", r.id);
+            Vec::new()
+        });
+        let role_ids = role_ids_from_reminder_id(data, r.id).await.unwrap_or_else(|e| {
+            tracing::warn!("{} failed to fetch roles for the digest: {e}", r.id);
+            Vec::new()
+        });
+        for user_id in &user_ids {
+            if !ping_users.contains(user_id) {
+                ping_users.push(*user_id);
+            }
+        }
+        for role_id in role_ids {
+            if !ping_roles.contains(&role_id) {
+                ping_roles.push(role_id);
+            }
+        }
+        followers.push(user_ids);
+    }
+    let mut ping_content = String::new();
+    for user_id in &ping_users {
+        write!(ping_content, "<@{user_id}> ").unwrap();
+    }
+    for role_id in &ping_roles {
+        write!(ping_content, "<@&{role_id}> ").unwrap();
+    }
+    let embed = reminder_embed(ctx)
+        .title("While I was away...")
+        .description(digest_description(reminders, now));
+    let allowed_mentions = CreateAllowedMentions::new().users(ping_users).roles(ping_roles);
+    let message =
+        CreateMessage::new().embed(embed).content(ping_content).allowed_mentions(allowed_mentions);
+    if let Err(e) = channel.send_message(ctx, message).await {
+        tracing::warn!("digest for {channel} failed, using DMs: {e}");
+        return false;
+    }
+    let outcome = DeliveryOutcome::Channel(channel);
+    for (r, user_ids) in reminders.iter().zip(followers) {
+        for user_id in user_ids {
+            record_reminded(data, r, user_id, 1, &outcome).await;
+        }
+    }
+    true
+}
+
+/// One DM with every reminder the user missed, failed digests are retried one reminder at a time
+async fn send_digest(
+    ctx: &Context, data: &Arc<Data>, user_id: UserId, reminders: &[&DueReminder], now: i64,
+) {
+    let embed = reminder_embed(ctx)
+        .title("While I was away...")
+        .description(digest_description(reminders, now));
+    let outcome = match user_id.direct_message(ctx, CreateMessage::new().embed(embed)).await {
+        Ok(_) => DeliveryOutcome::Dm,
+        Err(e) => DeliveryOutcome::Failed(e.to_string()),
+    };
+    // Persistent reminders start nagging from here like they would after a normal delivery
+    for r in reminders {
+        record_reminded(data, r, user_id, 1, &outcome).await;
+    }
+}
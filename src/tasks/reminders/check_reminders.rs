@@ -1,86 +1,681 @@
-use crate::commands::reminders::util::{get_next_reminder_ts, user_ids_from_reminder_id};
-use crate::{Data, BOT_COLOR};
+use crate::commands::reminders::recurrence::parse_recurrence;
+use crate::commands::reminders::util::{
+    role_ids_from_reminder_id, user_ids_from_reminder_id, MAX_NAGS,
+};
+use crate::commands::util::{get_internal_user_id, UserTimezone};
+use crate::events::reminders::{nag_components, snooze_components};
+use crate::tasks::reminders::deliveries::{record_delivery, DeliveryKind, DeliveryOutcome};
+use crate::{Data, Error, BOT_COLOR};
 use chrono::Utc;
-use poise::serenity_prelude::{ChannelId, Context, CreateEmbed, CreateEmbedAuthor, CreateMessage};
-use sqlx::query;
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateActionRow, CreateAllowedMentions, CreateEmbed, CreateEmbedAuthor,
+    CreateMessage, GuildId, RoleId, UserId,
+};
+use sqlx::{query, query_as};
+use std::collections::HashMap;
 use std::fmt::Write;
 use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use crate::util::url_guild_id;
 
+const MAX_CONCURRENT_DMS: usize = 10;
+const MAX_DELIVERY_ATTEMPTS: i64 = 5;
+const REMINDER_RETRY_SECONDS: i64 = 60; // doubled after every failed attempt
+
+pub struct DueReminder {
+    pub id: i64,
+    pub message: String,
+    pub timestamp: i64,
+    pub channel_id: i64,
+    pub guild_id: i64,
+    pub message_id: i64,
+    fallback_channel: Option<i64>,
+    pub delivery_channel: Option<i64>,
+    recurrence: Option<String>,
+    utc_offset: i64,
+    timezone: Option<String>,
+    grace_minutes: i64,
+    nag_minutes: Option<i64>,
+}
+
+/// Due reminders missing the rows needed to deliver them would otherwise be skipped by the JOINs
+/// below and stay due forever
+async fn deactivate_broken_reminders(data: &Arc<Data>, now: i64) {
+    let result = query!(
+        r"UPDATE reminders SET active = 0, removed_at = ?1
+        WHERE active = 1 AND timestamp <= ?1 AND (
+            NOT EXISTS (SELECT 1 FROM reminder_channel rc JOIN channels c ON rc.channel_id = c.id WHERE rc.reminder_id = reminders.id)
+            OR NOT EXISTS (SELECT 1 FROM reminder_guild rg JOIN guilds g ON rg.guild_id = g.id WHERE rg.reminder_id = reminders.id)
+            OR (NOT EXISTS (SELECT 1 FROM reminder_user ru WHERE ru.reminder_id = reminders.id)
+                AND NOT EXISTS (SELECT 1 FROM reminder_role rr WHERE rr.reminder_id = reminders.id))
+        )",
+        now
+    )
+    .execute(&data.pool)
+    .await;
+    match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            tracing::warn!("deactivated {} inconsistent reminders", result.rows_affected());
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("failed to clean up inconsistent reminders: {e}"),
+    }
+}
+
 pub async fn check_reminders(ctx: &Context, data: &Arc<Data>) {
-    let Some(next_timestamp) = *data.next_reminder.lock().unwrap() else {
-        return;
+    let now = Utc::now().timestamp();
+    deactivate_broken_reminders(data, now).await;
+    let due_reminders = match query_as!(
+        DueReminder,
+        r"SELECT r.id, message, timestamp, c.discord_id AS channel_id, g.discord_id AS guild_id, message_id, fc.discord_id AS fallback_channel, dc.discord_id AS delivery_channel, recurrence, r.utc_offset, r.timezone, g.stale_grace_minutes AS grace_minutes, r.nag_minutes
+        FROM reminders r
+        JOIN reminder_channel rc ON rc.reminder_id = r.id JOIN channels c ON rc.channel_id = c.id
+        JOIN reminder_guild rg ON r.id = rg.reminder_id JOIN guilds g ON rg.guild_id = g.id
+        LEFT JOIN channels fc ON fc.id = g.fallback_channel
+        LEFT JOIN channels dc ON dc.id = r.delivery_channel
+        WHERE active = 1 AND timestamp <= ?1 AND (r.next_attempt_at IS NULL OR r.next_attempt_at <= ?1)
+        ORDER BY timestamp ASC", now).fetch_all(&data.pool).await {
+        Ok(due_reminders) => due_reminders,
+        Err(e) => {
+            tracing::warn!("failed to fetch due reminders: {e}");
+            return; // try again on the next wake-up
+        }
     };
-    if next_timestamp > Utc::now().timestamp() {
-        return;
+
+    // Every due reminder is delivered in this pass, the semaphore keeps us from flooding discord with DMs
+    let dm_permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DMS));
+    let mut deliveries = JoinSet::new();
+    for r in due_reminders {
+        let reminder_id = r.id;
+        let delivery =
+            tokio::spawn(deliver_reminder(ctx.clone(), data.clone(), dm_permits.clone(), r));
+        // Awaiting the delivery in its own task catches panics while keeping track of the reminder
+        deliveries.spawn(async move { (reminder_id, delivery.await) });
+    }
+    while let Some(result) = deliveries.join_next().await {
+        let (reminder_id, error) = match result {
+            Ok((_, Ok(Ok(())))) => continue,
+            Ok((reminder_id, Ok(Err(e)))) => (reminder_id, e.to_string()),
+            Ok((reminder_id, Err(e))) => (reminder_id, e.to_string()),
+            Err(e) => {
+                tracing::error!("reminder delivery task failed: {e}");
+                continue;
+            }
+        };
+        tracing::warn!("{reminder_id} failed to deliver: {error}");
+        record_failed_delivery(data, reminder_id).await;
+    }
+    send_due_warnings(ctx, data, &dm_permits, now).await;
+    send_due_nags(ctx, data, &dm_permits, now).await;
+    retry_failed_deliveries(ctx, data, &dm_permits, now).await;
+}
+
+/// Advance warnings go out the way the reminder itself will, in its channel or by DM
+async fn send_due_warnings(ctx: &Context, data: &Arc<Data>, dm_permits: &Semaphore, now: i64) {
+    let warnings = match query!(
+        r"SELECT w.id, w.reminder_id, r.timestamp, u.discord_id AS user_id
+        FROM reminder_warnings w
+        JOIN reminders r ON r.id = w.reminder_id
+        JOIN reminder_user ru ON ru.reminder_id = w.reminder_id AND ru.user_id = w.user_id
+        JOIN users u ON u.id = w.user_id
+        WHERE r.active = 1 AND r.timestamp > ?1 AND r.timestamp - w.lead_seconds <= ?1
+            AND (w.warned_occurrence IS NULL OR w.warned_occurrence != r.timestamp)",
+        now
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(warnings) => warnings,
+        Err(e) => {
+            tracing::warn!("failed to fetch due warnings: {e}");
+            return;
+        }
     };
+    // Several warnings due at once, e.g. after being offline, only need one heads-up
+    let mut due = HashMap::<i64, Vec<UserId>>::new();
+    for warning in &warnings {
+        let user_id = UserId::new(warning.user_id as u64);
+        let user_ids = due.entry(warning.reminder_id).or_default();
+        if !user_ids.contains(&user_id) {
+            user_ids.push(user_id);
+        }
+    }
+    for (reminder_id, user_ids) in due {
+        if let Err(e) = send_warning(ctx, data, dm_permits, reminder_id, user_ids).await {
+            tracing::warn!("{reminder_id} failed to send warnings: {e}");
+        }
+    }
+    // Marked even when it failed, a late heads-up isn't worth retrying
+    for warning in warnings {
+        if let Err(e) = query!(
+            "UPDATE reminder_warnings SET warned_occurrence = ? WHERE id = ?",
+            warning.timestamp,
+            warning.id
+        )
+        .execute(&data.pool)
+        .await
+        {
+            tracing::warn!("{} failed to record warning: {e}", warning.reminder_id);
+        }
+    }
+}
 
-    let embed = CreateEmbed::new().color(BOT_COLOR).author(
-        CreateEmbedAuthor::new("Reminder notification!").icon_url(ctx.cache.current_user().face()),
-    );
-    let mut dm_disabled_users = Vec::new();
+async fn send_warning(
+    ctx: &Context, data: &Arc<Data>, dm_permits: &Semaphore, reminder_id: i64,
+    user_ids: Vec<UserId>,
+) -> Result<(), Error> {
+    let r = fetch_reminder(data, reminder_id).await?;
+    if let Some(delivery_channel) = r.delivery_channel {
+        let delivery_channel = ChannelId::new(delivery_channel as u64);
+        let kind = DeliveryKind::Warning;
+        if post_in_channel(ctx, data, &r, kind, delivery_channel, &user_ids, &[]).await {
+            return Ok(());
+        }
+    }
+    deliver_to_users(ctx, data, dm_permits, &r, DeliveryKind::Warning, user_ids, 1).await
+}
 
-    let r = query!( // First upcoming reminder
-        r"SELECT r.id, message, timestamp, created_at, c.discord_id AS channel_id, g.discord_id AS guild_id, message_id, fc.discord_id AS fallback_channel
+/// Persistent reminders come back every `nag_minutes` until acknowledged or out of repeats
+async fn send_due_nags(ctx: &Context, data: &Arc<Data>, dm_permits: &Semaphore, now: i64) {
+    let nags = match query!(
+        r"SELECT n.id, n.reminder_id, n.occurrence, n.repeats, u.discord_id AS user_id
+        FROM nags n JOIN users u ON u.id = n.user_id
+        WHERE n.next_nag_at <= ?",
+        now
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(nags) => nags,
+        Err(e) => {
+            tracing::warn!("failed to fetch due nags: {e}");
+            return;
+        }
+    };
+    for nag in nags {
+        let user_id = UserId::new(nag.user_id as u64);
+        let repeats = nag.repeats + 1;
+        let next_nag_at = match fetch_reminder(data, nag.reminder_id).await {
+            Ok(mut r) => {
+                r.timestamp = nag.occurrence;
+                if let Err(e) = send_nag(ctx, dm_permits, &r, user_id, repeats).await {
+                    tracing::warn!("{} failed to nag {user_id}: {e}", r.id);
+                }
+                // A failed nag still counts, so an unreachable user isn't retried forever
+                r.nag_minutes.filter(|_| repeats < MAX_NAGS).map(|minutes| now + minutes * 60)
+            }
+            Err(e) => {
+                tracing::warn!("{} can't nag {user_id}: {e}", nag.reminder_id);
+                None
+            }
+        };
+        if let Err(e) = query!(
+            "UPDATE nags SET repeats = ?, next_nag_at = ? WHERE id = ?",
+            repeats,
+            next_nag_at,
+            nag.id
+        )
+        .execute(&data.pool)
+        .await
+        {
+            tracing::warn!("{} failed to record nag: {e}", nag.reminder_id);
+        }
+    }
+}
+
+async fn send_nag(
+    ctx: &Context, dm_permits: &Semaphore, r: &DueReminder, user_id: UserId, repeats: i64,
+) -> Result<(), Error> {
+    let last_time = if repeats >= MAX_NAGS { " Th-this is the last time I'll ask..." } else { "" };
+    let embed = reminder_embed(ctx).description(format!(
+        "Um, s-sorry to bug you again.. <t:{0}:R> on <t:{0}:F>, you asked me to remind you about {1}, and you haven't told me you did it yet. Y-you can [view the context](https://hitori.discord.com/channels/{2}/{3}/{4}) if you need to.. P-please press \"I did it\" once it's done!{5}",
+        r.timestamp, r.message, url_guild_id(r.guild_id), r.channel_id, r.message_id, last_time
+    ));
+    send_to_user(ctx, dm_permits, r, user_id, embed, delivery_components(r)).await
+}
+
+/// DMs a single user, pinging them in the guild's fallback channel if that doesn't work
+async fn send_to_user(
+    ctx: &Context, dm_permits: &Semaphore, r: &DueReminder, user_id: UserId, embed: CreateEmbed,
+    components: Vec<CreateActionRow>,
+) -> Result<(), Error> {
+    let dm_result = {
+        let _permit = dm_permits.acquire().await?;
+        let message = CreateMessage::new().embed(embed.clone()).components(components.clone());
+        user_id.direct_message(ctx, message).await
+    };
+    let Err(dm_error) = dm_result else {
+        return Ok(());
+    };
+    let Some(fallback_channel) = r.fallback_channel else {
+        return Err(format!("{dm_error}; no fallback channel").into());
+    };
+    let message = CreateMessage::new()
+        .embed(embed)
+        .content(format!("<@{user_id}>"))
+        .allowed_mentions(CreateAllowedMentions::new().users([user_id]))
+        .components(components);
+    if let Err(e) = ChannelId::new(fallback_channel as u64).send_message(ctx, message).await {
+        return Err(format!("{dm_error}; {e}").into());
+    }
+    Ok(())
+}
+
+/// Users who couldn't be reached are retried on their own, the reminder itself already moved on
+async fn retry_failed_deliveries(
+    ctx: &Context, data: &Arc<Data>, dm_permits: &Semaphore, now: i64,
+) {
+    // Removed reminders and users who unfollowed aren't waiting for anything anymore. Role members
+    // aren't stored per reminder, so anyone is kept while the reminder still pings a role
+    if let Err(e) = query!(
+        r"UPDATE deliveries SET next_attempt_at = NULL
+        WHERE next_attempt_at <= ?1 AND (
+            EXISTS (SELECT 1 FROM reminders r WHERE r.id = deliveries.reminder_id AND r.removed_at IS NOT NULL)
+            OR (NOT EXISTS (SELECT 1 FROM reminder_user ru WHERE ru.reminder_id = deliveries.reminder_id AND ru.user_id = deliveries.user_id)
+                AND NOT EXISTS (SELECT 1 FROM reminder_role rr WHERE rr.reminder_id = deliveries.reminder_id))
+        )",
+        now
+    )
+    .execute(&data.pool)
+    .await
+    {
+        tracing::warn!("failed to cancel unwanted retries: {e}");
+    }
+    let retries = match query!(
+        r"SELECT d.id, d.reminder_id, d.occurrence, d.attempts, u.discord_id AS user_id
+        FROM deliveries d JOIN users u ON u.id = d.user_id
+        WHERE d.next_attempt_at <= ?",
+        now
+    )
+    .fetch_all(&data.pool)
+    .await
+    {
+        Ok(retries) => retries,
+        Err(e) => {
+            tracing::warn!("failed to fetch failed deliveries: {e}");
+            return;
+        }
+    };
+    for retry in retries {
+        let user_id = UserId::new(retry.user_id as u64);
+        // Cleared before sending, a failed retry schedules the next one when it's recorded. If
+        // recording fails the user misses a retry, rather than getting one on every wake-up
+        if let Err(e) =
+            query!("UPDATE deliveries SET next_attempt_at = NULL WHERE id = ?", retry.id)
+                .execute(&data.pool)
+                .await
+        {
+            tracing::warn!("{} failed to claim retry for {user_id}: {e}", retry.reminder_id);
+            continue;
+        }
+        let mut r = match fetch_reminder(data, retry.reminder_id).await {
+            Ok(r) => r,
+            Err(e) => {
+                // The reminder's rows are gone, there's nothing left to deliver
+                tracing::warn!("{} can't be retried for {user_id}: {e}", retry.reminder_id);
+                continue;
+            }
+        };
+        r.timestamp = retry.occurrence;
+        let (kind, attempt) = (DeliveryKind::Reminder, retry.attempts + 1);
+        if let Err(e) =
+            deliver_to_users(ctx, data, dm_permits, &r, kind, vec![user_id], attempt).await
+        {
+            tracing::warn!("{} failed to retry delivery to {user_id}: {e}", r.id);
+        }
+    }
+}
+
+pub async fn fetch_reminder(data: &Arc<Data>, reminder_id: i64) -> Result<DueReminder, Error> {
+    Ok(query_as!(
+        DueReminder,
+        r"SELECT r.id, message, timestamp, c.discord_id AS channel_id, g.discord_id AS guild_id, message_id, fc.discord_id AS fallback_channel, dc.discord_id AS delivery_channel, recurrence, r.utc_offset, r.timezone, g.stale_grace_minutes AS grace_minutes, r.nag_minutes
         FROM reminders r
         JOIN reminder_channel rc ON rc.reminder_id = r.id JOIN channels c ON rc.channel_id = c.id
         JOIN reminder_guild rg ON r.id = rg.reminder_id JOIN guilds g ON rg.guild_id = g.id
-        LEFT JOIN channels fc ON fc.id = g.fallback_channel 
-        WHERE active = 1 ORDER BY timestamp ASC LIMIT 1").fetch_one(&data.pool).await.unwrap(); // unwrap because tbh shit's joever if this fails
-    let user_ids = user_ids_from_reminder_id(data, r.id).await.unwrap();
+        LEFT JOIN channels fc ON fc.id = g.fallback_channel
+        LEFT JOIN channels dc ON dc.id = r.delivery_channel
+        WHERE r.id = ?", reminder_id).fetch_one(&data.pool).await?)
+}
+
+/// A failed reminder waits a little longer before every retry, and is quarantined once it keeps
+/// failing
+async fn record_failed_delivery(data: &Arc<Data>, reminder_id: i64) {
+    let now = Utc::now().timestamp();
+    if let Err(e) = query!(
+        r"UPDATE reminders SET failed_attempts = failed_attempts + 1,
+            next_attempt_at = ? + (? << failed_attempts)
+        WHERE id = ?",
+        now,
+        REMINDER_RETRY_SECONDS,
+        reminder_id
+    )
+    .execute(&data.pool)
+    .await
+    {
+        tracing::error!("{reminder_id} failed to record delivery failure: {e}");
+        return;
+    }
+    match query!(
+        "UPDATE reminders SET active = 0, quarantined = 1 WHERE id = ? AND failed_attempts >= ?",
+        reminder_id,
+        MAX_DELIVERY_ATTEMPTS
+    )
+    .execute(&data.pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() > 0 => tracing::error!(
+            "{reminder_id} quarantined after {MAX_DELIVERY_ATTEMPTS} failed delivery attempts"
+        ),
+        Ok(_) => {}
+        Err(e) => tracing::error!("{reminder_id} failed to quarantine: {e}"),
+    }
+}
+
+/// "2 hours and 5 minutes", only the two largest units
+pub fn format_lateness(seconds: i64) -> String {
+    let mut parts = Vec::new();
+    let mut remaining = seconds;
+    for (unit_seconds, unit) in [(86400, "day"), (3600, "hour"), (60, "minute")] {
+        let amount = remaining / unit_seconds;
+        remaining %= unit_seconds;
+        if amount > 0 {
+            parts.push(format!("{amount} {unit}{}", if amount == 1 { "" } else { "s" }));
+        }
+        if parts.len() == 2 {
+            break;
+        }
+    }
+    if parts.is_empty() {
+        return "less than a minute".to_string();
+    }
+    parts.join(" and ")
+}
+
+/// Reminders that go out after the guild's grace period say how late they are
+fn late_note(r: &DueReminder) -> String {
+    let late_seconds = Utc::now().timestamp() - r.timestamp;
+    if late_seconds <= r.grace_minutes * 60 {
+        return String::new();
+    }
+    format!(
+        "\nS-sorry, this is {} late... I-I was away when it was due.",
+        format_lateness(late_seconds)
+    )
+}
+
+fn nag_note(r: &DueReminder) -> String {
+    match r.nag_minutes {
+        Some(nag_minutes) => format!(
+            "\nP-please press \"I did it\" once it's done, or I'll keep reminding you every {nag_minutes} minutes..."
+        ),
+        None => String::new(),
+    }
+}
 
+/// Snooze buttons, and the "I did it" button for persistent reminders
+fn delivery_components(r: &DueReminder) -> Vec<CreateActionRow> {
+    match r.nag_minutes {
+        Some(_) => vec![nag_components(r.id, r.timestamp), snooze_components(r.id)],
+        None => vec![snooze_components(r.id)],
+    }
+}
+
+/// Logs the delivery, and starts nagging whoever got a persistent reminder until they acknowledge it
+pub async fn record_reminded(
+    data: &Arc<Data>, r: &DueReminder, user_id: UserId, attempt: i64, outcome: &DeliveryOutcome,
+) {
+    record_delivery(data, DeliveryKind::Reminder, r.id, r.timestamp, user_id, attempt, outcome)
+        .await;
+    let Some(nag_minutes) = r.nag_minutes else {
+        return;
+    };
+    if matches!(outcome, DeliveryOutcome::Failed(_) | DeliveryOutcome::Dropped) {
+        return;
+    }
+    let next_nag_at = Utc::now().timestamp() + nag_minutes * 60;
+    let result = async {
+        let i_user_id = get_internal_user_id(data, user_id).await?;
+        query!(
+            r"INSERT INTO nags (reminder_id, user_id, occurrence, next_nag_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT (reminder_id, user_id, occurrence) DO NOTHING",
+            r.id,
+            i_user_id,
+            r.timestamp,
+            next_nag_at
+        )
+        .execute(&data.pool)
+        .await?;
+        Ok::<(), Error>(())
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("{} failed to start nagging {user_id}: {e}", r.id);
+    }
+}
+
+pub fn reminder_embed(ctx: &Context) -> CreateEmbed {
+    CreateEmbed::new().color(BOT_COLOR).author(
+        CreateEmbedAuthor::new("Reminder notification!").icon_url(ctx.cache.current_user().face()),
+    )
+}
+
+/// The reminder itself, or the heads-up before it. `username` greets a single user by name
+fn notice_embed(
+    ctx: &Context, r: &DueReminder, kind: DeliveryKind, username: Option<&str>, late: &str,
+) -> CreateEmbed {
+    let greeting = username.map(|username| format!(" {username}")).unwrap_or_default();
+    match kind {
+        DeliveryKind::Reminder => reminder_embed(ctx).description(format!(
+            "Um, h-hey{0}.. <t:{1}:R> on <t:{1}:F>, you asked me to remind you about {2}. Y-you can [view the context](https://hitori.discord.com/channels/{3}/{4}/{5}) if you need to.. I-I hope that helps!{6}{7}",
+            greeting, r.timestamp, r.message, url_guild_id(r.guild_id), r.channel_id, r.message_id, late, nag_note(r)
+        )),
+        DeliveryKind::Warning => reminder_embed(ctx).title("Heads-up!").description(format!(
+            "Um, j-just a heads-up{0}.. <t:{1}:R>, at <t:{1}:F>, I'll remind you about {2}. Y-you can [view the context](https://hitori.discord.com/channels/{3}/{4}/{5}) if you need to..",
+            greeting, r.timestamp, r.message, url_guild_id(r.guild_id), r.channel_id, r.message_id
+        )),
+    }
+}
+
+/// A heads-up has nothing to snooze or acknowledge yet
+fn notice_components(r: &DueReminder, kind: DeliveryKind) -> Vec<CreateActionRow> {
+    match kind {
+        DeliveryKind::Reminder => delivery_components(r),
+        DeliveryKind::Warning => Vec::new(),
+    }
+}
+
+async fn record_notice(
+    data: &Arc<Data>, r: &DueReminder, kind: DeliveryKind, user_id: UserId, attempt: i64,
+    outcome: &DeliveryOutcome,
+) {
+    match kind {
+        DeliveryKind::Reminder => record_reminded(data, r, user_id, attempt, outcome).await,
+        DeliveryKind::Warning => {
+            record_delivery(data, kind, r.id, r.timestamp, user_id, attempt, outcome).await;
+        }
+    }
+}
+
+/// Channel reminders ping every follower and role in one message. Returns false if it couldn't be
+/// posted, DMs are the fallback then
+async fn post_in_channel(
+    ctx: &Context, data: &Arc<Data>, r: &DueReminder, kind: DeliveryKind, channel: ChannelId,
+    user_ids: &[UserId], role_ids: &[RoleId],
+) -> bool {
+    let mut ping_content = String::new();
+    for user_id in user_ids {
+        write!(ping_content, "<@{user_id}> ").unwrap();
+    }
+    for role_id in role_ids {
+        write!(ping_content, "<@&{role_id}> ").unwrap();
+    }
+    let allowed_mentions = CreateAllowedMentions::new()
+        .users(user_ids.iter().copied())
+        .roles(role_ids.iter().copied());
+    let message = CreateMessage::new()
+        .embed(notice_embed(ctx, r, kind, None, &late_note(r)))
+        .content(ping_content)
+        .allowed_mentions(allowed_mentions)
+        .components(notice_components(r, kind));
+    match channel.send_message(ctx, message).await {
+        Ok(_) => {
+            let outcome = DeliveryOutcome::Channel(channel);
+            for user_id in user_ids {
+                record_notice(data, r, kind, *user_id, 1, &outcome).await;
+            }
+            true
+        }
+        Err(e) => {
+            tracing::warn!("{} channel delivery failed, using DMs: {e}", r.id);
+            false
+        }
+    }
+}
+
+async fn deliver_reminder(
+    ctx: Context, data: Arc<Data>, dm_permits: Arc<Semaphore>, r: DueReminder,
+) -> Result<(), Error> {
+    let mut user_ids = user_ids_from_reminder_id(&data, r.id).await?;
+    let role_ids = role_ids_from_reminder_id(&data, r.id).await?;
+    // A retry only goes to whoever the failed attempt didn't get to, users it couldn't reach
+    // already have retries of their own
+    let attempted = query!(
+        r"SELECT u.discord_id AS user_id, d.status FROM deliveries d JOIN users u ON u.id = d.user_id
+        WHERE d.reminder_id = ? AND d.occurrence = ? AND d.kind = 'reminder'",
+        r.id,
+        r.timestamp
+    )
+    .fetch_all(&data.pool)
+    .await?;
+    if attempted.iter().any(|delivery| delivery.status == "channel") {
+        return finish_reminder(&data, &r).await;
+    }
+    let attempted: Vec<UserId> =
+        attempted.iter().map(|delivery| UserId::new(delivery.user_id as u64)).collect();
+    user_ids.retain(|user_id| !attempted.contains(user_id));
+
+    if let Some(delivery_channel) = r.delivery_channel {
+        let delivery_channel = ChannelId::new(delivery_channel as u64);
+        let kind = DeliveryKind::Reminder;
+        if post_in_channel(&ctx, &data, &r, kind, delivery_channel, &user_ids, &role_ids).await {
+            return finish_reminder(&data, &r).await;
+        }
+    }
+
+    if !role_ids.is_empty() {
+        for member_id in role_member_ids(&ctx, r.guild_id, &role_ids) {
+            if !user_ids.contains(&member_id) && !attempted.contains(&member_id) {
+                user_ids.push(member_id);
+            }
+        }
+    }
+
+    deliver_to_users(&ctx, &data, &dm_permits, &r, DeliveryKind::Reminder, user_ids, 1).await?;
+    finish_reminder(&data, &r).await
+}
+
+/// DMs every user, pinging the ones that can't be DMed in the guild's fallback channel instead
+async fn deliver_to_users(
+    ctx: &Context, data: &Arc<Data>, dm_permits: &Semaphore, r: &DueReminder, kind: DeliveryKind,
+    user_ids: Vec<UserId>, attempt: i64,
+) -> Result<(), Error> {
+    // Retries are late on purpose, that isn't worth apologizing for
+    let late = if attempt == 1 { late_note(r) } else { String::new() };
+    let mut dm_disabled_users = Vec::new();
     for user_id in user_ids {
         let username = match user_id.to_user(ctx).await {
             Ok(username) => username.name,
-            Err(_) => continue,
+            Err(e) => {
+                let outcome = DeliveryOutcome::Failed(e.to_string());
+                record_delivery(data, kind, r.id, r.timestamp, user_id, attempt, &outcome).await;
+                continue;
+            }
         };
-        let embed = embed.clone().description(format!(
-            "Um, h-hey {0}.. <t:{1}:R> on <t:{1}:F>, you asked me to remind you about {2}. Y-you can [view the context](https://hitori.discord.com/channels/{3}/{4}/{5}) if you need to.. I-I hope that helps!",
-            username, r.timestamp, r.message, url_guild_id(r.guild_id), r.channel_id, r.message_id
-        ));
-        if user_id.direct_message(ctx, CreateMessage::new().embed(embed)).await.is_err() {
-            dm_disabled_users.push(user_id);
-        }
-    }
-    if !dm_disabled_users.is_empty() && r.fallback_channel.is_some() {
-        let fallback_channel = ChannelId::new(r.fallback_channel.unwrap() as u64);
-        let embed = embed.clone().description(format!(
-            "Um, h-hey.. <t:{0}:R> on <t:{0}:F>, you asked me to remind you about {1}. Y-you can [view the context](https://hitori.discord.com/channels/{2}/{3}/{4}) if you need to.. I-I hope that helps!",
-            r.timestamp, r.message, url_guild_id(r.guild_id), r.channel_id, r.message_id
-        ));
-        let mut ping_content = String::new();
-        for no_dm_user in dm_disabled_users {
-            write!(ping_content, "<@{no_dm_user}> ").unwrap();
-        }
-        let _ = fallback_channel
-            .send_message(ctx, CreateMessage::new().embed(embed).content(ping_content))
-            .await; // continue even if it can't send the message
-    }
-
-    if query!("UPDATE reminders SET active = 0 WHERE id = ?", r.id)
-        .execute(&data.pool)
-        .await
-        .is_err()
-    {
-        tracing::warn!("{} failed to remove from database", r.id);
+        let embed = notice_embed(ctx, r, kind, Some(&username), &late);
+        let _permit = dm_permits.acquire().await?;
+        let message = CreateMessage::new().embed(embed).components(notice_components(r, kind));
+        match user_id.direct_message(ctx, message).await {
+            Ok(_) => record_notice(data, r, kind, user_id, attempt, &DeliveryOutcome::Dm).await,
+            Err(e) => dm_disabled_users.push((user_id, e.to_string())),
+        }
+    }
+    if dm_disabled_users.is_empty() {
+        return Ok(());
+    }
+
+    let fallback_result = match r.fallback_channel {
+        Some(fallback_channel) => {
+            let fallback_channel = ChannelId::new(fallback_channel as u64);
+            let mut ping_content = String::new();
+            for (no_dm_user, _) in &dm_disabled_users {
+                write!(ping_content, "<@{no_dm_user}> ").unwrap();
+            }
+            fallback_channel
+                .send_message(
+                    ctx,
+                    CreateMessage::new()
+                        .embed(notice_embed(ctx, r, kind, None, &late))
+                        .content(ping_content)
+                        .components(notice_components(r, kind)),
+                )
+                .await
+                .map(|_| fallback_channel)
+                .map_err(|e| e.to_string())
+        }
+        None => Err("no fallback channel".to_string()),
     };
+    for (user_id, dm_error) in dm_disabled_users {
+        let outcome = match &fallback_result {
+            Ok(fallback_channel) => DeliveryOutcome::Fallback(*fallback_channel),
+            Err(e) => DeliveryOutcome::Failed(format!("{dm_error}; {e}")),
+        };
+        record_notice(data, r, kind, user_id, attempt, &outcome).await;
+    }
+    Ok(())
+}
 
-    let upcoming_reminder = get_next_reminder_ts(&data.pool).await;
-    let mut stored_reminder = data.next_reminder.lock().unwrap();
-    let Some(stored_reminder_timestamp) = *stored_reminder else {
-        // Nothing in cache, replace with the next reminder or None
-        *stored_reminder = upcoming_reminder;
-        return;
+/// Role members are looked up at delivery time, so people who got the role after the reminder
+/// was created are reminded too. The member list comes from the cache, see `event_handler`
+pub fn role_member_ids(ctx: &Context, guild_id: i64, role_ids: &[RoleId]) -> Vec<UserId> {
+    if guild_id <= 0 {
+        return Vec::new(); // roles only exist in guilds
+    }
+    let guild_id = GuildId::new(guild_id as u64);
+    let Some(guild) = ctx.cache.guild(guild_id) else {
+        tracing::warn!("{guild_id} isn't cached, can't find its role members");
+        return Vec::new();
     };
-    if next_timestamp == stored_reminder_timestamp {
-        // reminder that just finished was in the cache, replace it with the soonest one found in db
-        *stored_reminder = upcoming_reminder;
+    guild
+        .members
+        .values()
+        .filter(|member| !member.user.bot)
+        .filter(|member| member.roles.iter().any(|role| role_ids.contains(role)))
+        .map(|member| member.user.id)
+        .collect()
+}
+
+pub async fn finish_reminder(data: &Arc<Data>, r: &DueReminder) -> Result<(), Error> {
+    // Recurring reminders move on to their next occurrence instead of expiring
+    let next_occurrence = r.recurrence.as_deref().and_then(|rule| {
+        let recurrence = parse_recurrence(&data.regex_cache, rule).ok()?;
+        recurrence.next_occurrence(
+            r.timestamp,
+            Utc::now().timestamp(),
+            UserTimezone::from_db(r.utc_offset, r.timezone.as_deref()),
+        )
+    });
+    if let Some(next_occurrence) = next_occurrence {
+        query!(
+            "UPDATE reminders SET timestamp = ?, failed_attempts = 0, next_attempt_at = NULL WHERE id = ?",
+            next_occurrence,
+            r.id
+        )
+        .execute(&data.pool)
+        .await?;
     } else {
-        // Race condition happened, make sure the earliest reminder is next
-        if let Some(upcoming_reminder_timestamp) = upcoming_reminder {
-            if upcoming_reminder_timestamp < stored_reminder_timestamp {
-                *stored_reminder = upcoming_reminder;
-            }
-        }
+        query!("UPDATE reminders SET active = 0 WHERE id = ?", r.id).execute(&data.pool).await?;
     }
+    Ok(())
 }
@@ -0,0 +1,87 @@
+use crate::commands::util::{get_internal_channel_id, get_internal_user_id};
+use crate::{Data, Error};
+use chrono::Utc;
+use poise::serenity_prelude::{ChannelId, UserId};
+use sqlx::query;
+use std::sync::Arc;
+
+pub const MAX_DELIVERY_RETRIES: i64 = 3;
+const RETRY_DELAY_SECONDS: i64 = 300; // doubled after every failed attempt
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryKind {
+    Reminder,
+    /// An advance warning before the reminder, see `set_warnings`
+    Warning,
+}
+
+impl DeliveryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            DeliveryKind::Reminder => "reminder",
+            DeliveryKind::Warning => "warning",
+        }
+    }
+}
+
+pub enum DeliveryOutcome {
+    Channel(ChannelId),
+    Dm,
+    Fallback(ChannelId),
+    Failed(String),
+    /// The reminder was stale when the bot came back and the guild chose to skip those
+    Dropped,
+}
+
+/// Logs one attempt at reminding a user, `attempt` starts at 1 and failed attempts are scheduled
+/// for a retry until `MAX_DELIVERY_RETRIES` is used up. Warnings aren't retried, a late heads-up
+/// isn't worth it
+pub async fn record_delivery(
+    data: &Arc<Data>, kind: DeliveryKind, reminder_id: i64, occurrence: i64, user: UserId,
+    attempt: i64, outcome: &DeliveryOutcome,
+) {
+    let now = Utc::now().timestamp();
+    let (status, channel, error) = match outcome {
+        DeliveryOutcome::Channel(channel) => ("channel", Some(*channel), None),
+        DeliveryOutcome::Dm => ("dm", None, None),
+        DeliveryOutcome::Fallback(channel) => ("fallback", Some(*channel), None),
+        DeliveryOutcome::Failed(error) => ("failed", None, Some(error.as_str())),
+        DeliveryOutcome::Dropped => ("dropped", None, None),
+    };
+    let retry = kind == DeliveryKind::Reminder && error.is_some();
+    let next_attempt_at = (retry && attempt <= MAX_DELIVERY_RETRIES)
+        .then(|| now + (RETRY_DELAY_SECONDS << (attempt - 1)));
+
+    let kind_str = kind.as_str();
+    let result = async {
+        let i_user_id = get_internal_user_id(data, user).await?;
+        let i_channel_id = match channel {
+            Some(channel) => Some(get_internal_channel_id(data, channel).await?),
+            None => None,
+        };
+        query!(
+            r"INSERT INTO deliveries (reminder_id, user_id, occurrence, kind, channel_id, status, error, attempts, attempted_at, next_attempt_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (reminder_id, user_id, occurrence, kind) DO UPDATE SET
+            channel_id = excluded.channel_id, status = excluded.status, error = excluded.error, attempts = excluded.attempts,
+            attempted_at = excluded.attempted_at, next_attempt_at = excluded.next_attempt_at",
+            reminder_id,
+            i_user_id,
+            occurrence,
+            kind_str,
+            i_channel_id,
+            status,
+            error,
+            attempt,
+            now,
+            next_attempt_at
+        )
+        .execute(&data.pool)
+        .await?;
+        Ok::<(), Error>(())
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("{reminder_id} failed to log delivery to {user}: {e}");
+    }
+}
@@ -1,3 +1,6 @@
+pub use catch_up::catch_up_reminders;
 pub use check_reminders::check_reminders;
 
+mod catch_up;
 mod check_reminders;
+mod deliveries;
@@ -1,101 +1,383 @@
-use std::time::Duration;
 use crate::{Context, Error, BOT_COLOR};
 use poise::serenity_prelude::{
-    ComponentInteractionCollector, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedAuthor,
-    CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage, EmojiId,
+    ButtonStyle, ComponentInteraction, ComponentInteractionCollector, ComponentInteractionDataKind,
+    CreateActionRow, CreateButton, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
+    CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, EmojiId, Message,
     ReactionType,
 };
-use poise::CreateReply;
+use poise::{CreateReply, Modal};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
 
-fn create_page_embed(
-    ctx: Context<'_>, pages: &[Vec<String>], title: String, page: usize,
-) -> CreateEmbed {
-    CreateEmbed::default()
-        .color(BOT_COLOR)
-        .author(CreateEmbedAuthor::from(ctx.author().clone()))
-        .title(title)
-        .description(pages[page].join("\n"))
-        .footer(CreateEmbedFooter::new(format!(
-            "Page {}/{} - Showing entries {}-{} out of {}.",
-            page + 1,
-            pages.len(),
-            page * pages[0].len() + 1,
-            page * pages[0].len() + pages[page].len(),
-            pages[0].len() * (pages.len() - 1) + pages[pages.len() - 1].len()
-        )))
+// Edits after this go through the bot's own token, so this isn't bound to the 15 minute
+// interaction token lifetime
+const PAGINATION_TIMEOUT: Duration = Duration::from_secs(600);
+const JUMP_MODAL_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_SELECT_OPTIONS: usize = 25;
+const MAX_SELECT_LABEL_CHARS: usize = 100;
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub type PageFuture<'a> = Pin<Box<dyn Future<Output = Result<Page, Error>> + Send + 'a>>;
+pub type SelectFuture<'a> = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>;
+type PageLoader<'a> = Box<dyn Fn(usize) -> PageFuture<'a> + Send + Sync + 'a>;
+type SelectAction<'a> =
+    Box<dyn Fn(ComponentInteraction, String) -> SelectFuture<'a> + Send + Sync + 'a>;
+
+/// One page of entries, `items` are the choices for the select menu as (label, value)
+#[derive(Clone, Default)]
+pub struct Page {
+    pub lines: Vec<String>,
+    pub items: Vec<(String, String)>,
 }
 
-pub async fn paginate(
-    ctx: Context<'_>, pages: &[Vec<String>], title: String, mut page: usize,
-) -> Result<(), Error> {
-    // Define some unique identifiers for the navigation buttons
-    let ctx_id = ctx.id();
-    if page >= pages.len() {
-        page = 0;
-    }
-    let prev_button_id = format!("{ctx_id}prev");
-    let next_button_id = format!("{ctx_id}next");
-
-    // Send the embed with the first page as content
-    let mut reply =
-        { CreateReply::default().embed(create_page_embed(ctx, pages, title.clone(), page)) };
-
-    if pages.len() > 1 {
-        let components = CreateActionRow::Buttons(vec![
-            CreateButton::new(&prev_button_id).emoji(ReactionType::Custom {
-                animated: false,
-                id: EmojiId::new(1257787809633275954),
-                name: Some("bwaaa_left".into()),
-            }),
-            CreateButton::new(&next_button_id).emoji(ReactionType::Custom {
-                animated: false,
-                id: EmojiId::new(1257787824283844772),
-                name: Some("bwaaa_right".into()),
-            }),
-        ]);
-        reply = reply.components(vec![components]);
+impl From<Vec<String>> for Page {
+    fn from(lines: Vec<String>) -> Self {
+        Page { lines, items: Vec::new() }
     }
+}
+
+#[derive(Modal)]
+#[name = "Jump to page"]
+struct JumpModal {
+    #[name = "Page number"]
+    #[placeholder = "1"]
+    page: String,
+}
 
-    ctx.send(reply).await?;
+/// An embed with buttons to flip through pages, only the command author can use them
+pub struct Paginator<'a> {
+    title: String,
+    total_items: usize,
+    page_items: usize,
+    pages: PageLoader<'a>,
+    select: Option<(String, SelectAction<'a>)>,
+}
 
-    if pages.len() == 1 {
-        return Ok(());
+impl<'a> Paginator<'a> {
+    /// Pages are only loaded when they're first shown, `total_items` and `page_items` are
+    /// needed up front to know how many pages there are
+    pub fn new<F>(title: String, total_items: usize, page_items: usize, pages: F) -> Self
+    where
+        F: Fn(usize) -> PageFuture<'a> + Send + Sync + 'a,
+    {
+        Paginator {
+            title,
+            total_items,
+            page_items: page_items.max(1),
+            pages: Box::new(pages),
+            select: None,
+        }
     }
 
-    // Loop through incoming interactions with the navigation buttons
-    while let Some(press) = ComponentInteractionCollector::new(ctx)
-        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
-        // Timeout when no navigation button has been pressed for 24 hours
-        .timeout(Duration::from_secs(120))
-        .await
+    /// Adds a select menu with the items on the current page. The action has to respond to the
+    /// interaction itself, errors are shown to the user afterwards
+    pub fn select<F>(mut self, placeholder: impl Into<String>, action: F) -> Self
+    where
+        F: Fn(ComponentInteraction, String) -> SelectFuture<'a> + Send + Sync + 'a,
     {
-        // Depending on which button was pressed, go to next or previous page
-        if press.data.custom_id == next_button_id {
-            page += 1;
-            if page >= pages.len() {
-                page = 0;
-            }
+        self.select = Some((placeholder.into(), Box::new(action)));
+        self
+    }
+
+    fn page_count(&self) -> usize {
+        self.total_items.div_ceil(self.page_items).max(1)
+    }
+
+    async fn load(&self, cache: &mut HashMap<usize, Page>, page: usize) -> Result<Page, Error> {
+        if let Some(cached) = cache.get(&page) {
+            return Ok(cached.clone());
+        }
+        let loaded = (self.pages)(page).await?;
+        cache.insert(page, loaded.clone());
+        Ok(loaded)
+    }
+
+    fn embed(&self, ctx: Context<'_>, page: usize, current: &Page) -> CreateEmbed {
+        let description = if current.lines.is_empty() {
+            "Um, th-there's nothing on this page anymore...".to_string()
         } else {
-            page = page.checked_sub(1).unwrap_or(pages.len() - 1);
+            current.lines.join("\n")
+        };
+        let first_entry = page * self.page_items + 1;
+        CreateEmbed::default()
+            .color(BOT_COLOR)
+            .author(CreateEmbedAuthor::from(ctx.author().clone()))
+            .title(self.title.clone())
+            .description(description)
+            .footer(CreateEmbedFooter::new(format!(
+                "Page {}/{} - Showing entries {}-{} out of {}.",
+                page + 1,
+                self.page_count(),
+                first_entry,
+                first_entry + current.lines.len().saturating_sub(1),
+                self.total_items
+            )))
+    }
+
+    fn components(
+        &self, ctx_id: u64, page: usize, current: &Page, disabled: bool,
+    ) -> Vec<CreateActionRow> {
+        let mut rows = Vec::new();
+        let page_count = self.page_count();
+        if page_count > 1 {
+            rows.push(CreateActionRow::Buttons(vec![
+                CreateButton::new(format!("{ctx_id}first")).emoji('⏮').disabled(disabled),
+                CreateButton::new(format!("{ctx_id}prev"))
+                    .emoji(ReactionType::Custom {
+                        animated: false,
+                        id: EmojiId::new(1257787809633275954),
+                        name: Some("bwaaa_left".into()),
+                    })
+                    .disabled(disabled),
+                CreateButton::new(format!("{ctx_id}jump"))
+                    .label(format!("{}/{page_count}", page + 1))
+                    .style(ButtonStyle::Secondary)
+                    .disabled(disabled),
+                CreateButton::new(format!("{ctx_id}next"))
+                    .emoji(ReactionType::Custom {
+                        animated: false,
+                        id: EmojiId::new(1257787824283844772),
+                        name: Some("bwaaa_right".into()),
+                    })
+                    .disabled(disabled),
+                CreateButton::new(format!("{ctx_id}last")).emoji('⏭').disabled(disabled),
+            ]));
+        }
+        if let Some((placeholder, _)) = &self.select {
+            let options: Vec<CreateSelectMenuOption> = current
+                .items
+                .iter()
+                .take(MAX_SELECT_OPTIONS)
+                .map(|(label, value)| {
+                    let label: String = label.chars().take(MAX_SELECT_LABEL_CHARS).collect();
+                    CreateSelectMenuOption::new(label, value)
+                })
+                .collect();
+            // Discord rejects select menus without any options
+            if !options.is_empty() {
+                rows.push(CreateActionRow::SelectMenu(
+                    CreateSelectMenu::new(
+                        format!("{ctx_id}select"),
+                        CreateSelectMenuKind::String { options },
+                    )
+                    .placeholder(placeholder)
+                    .disabled(disabled),
+                ));
+            }
+        }
+        rows
+    }
+
+    /// Handles one press on the navigation, `page` and `current` only change once the new page
+    /// has loaded
+    async fn press(
+        &self, ctx: Context<'_>, press: &ComponentInteraction, message: &mut Message,
+        cache: &mut HashMap<usize, Page>, page: &mut usize, current: &mut Page,
+    ) -> Result<(), Error> {
+        let ctx_id = ctx.id();
+        let page_count = self.page_count();
+        if press.user.id != ctx.author().id {
+            let response = CreateInteractionResponseMessage::new()
+                .content("Um, th-these buttons belong to someone else... Y-you can use the command yourself, though!")
+                .ephemeral(true);
+            press
+                .create_response(
+                    ctx.serenity_context(),
+                    CreateInteractionResponse::Message(response),
+                )
+                .await?;
+            return Ok(());
         }
 
+        let button = &press.data.custom_id[ctx_id.to_string().len()..];
+        let next = match button {
+            "first" => 0,
+            "prev" => page.checked_sub(1).unwrap_or(page_count - 1),
+            "next" => (*page + 1) % page_count,
+            "last" => page_count - 1,
+            "jump" => {
+                // The modal takes over the interaction, so the page is updated by editing
+                let jump = poise::execute_modal_on_component_interaction::<JumpModal>(
+                    ctx,
+                    press.clone(),
+                    None,
+                    Some(JUMP_MODAL_TIMEOUT),
+                )
+                .await?;
+                let Some(jump) = jump else {
+                    return Ok(());
+                };
+                let next = match jump.page.trim().parse::<usize>() {
+                    Ok(jump_page) if (1..=page_count).contains(&jump_page) => jump_page - 1,
+                    _ => {
+                        let followup = CreateInteractionResponseFollowup::new()
+                            .content(format!("Uh, th-that page doesn't exist... C-could you pick one from 1 to {page_count}?"))
+                            .ephemeral(true);
+                        press.create_followup(ctx.serenity_context(), followup).await?;
+                        return Ok(());
+                    }
+                };
+                let loaded = self.load(cache, next).await?;
+                let edit = EditMessage::new()
+                    .embed(self.embed(ctx, next, &loaded))
+                    .components(self.components(ctx_id, next, &loaded, false));
+                message.edit(ctx.serenity_context(), edit).await?;
+                (*page, *current) = (next, loaded);
+                return Ok(());
+            }
+            "select" => {
+                let (Some((_, action)), ComponentInteractionDataKind::StringSelect { values }) =
+                    (&self.select, &press.data.kind)
+                else {
+                    return Ok(());
+                };
+                let Some(value) = values.first() else {
+                    return Ok(());
+                };
+                return action(press.clone(), value.clone()).await;
+            }
+            _ => return Ok(()),
+        };
+
         // Update the message with the new page contents
+        let loaded = self.load(cache, next).await?;
         press
             .create_response(
                 ctx.serenity_context(),
                 CreateInteractionResponse::UpdateMessage(
-                    CreateInteractionResponseMessage::new().embed(create_page_embed(
-                        ctx,
-                        pages,
-                        title.clone(),
-                        page,
-                    )),
+                    CreateInteractionResponseMessage::new()
+                        .embed(self.embed(ctx, next, &loaded))
+                        .components(self.components(ctx_id, next, &loaded, false)),
                 ),
             )
             .await?;
+        (*page, *current) = (next, loaded);
+        Ok(())
+    }
+
+    pub async fn run(self, ctx: Context<'_>, mut page: usize) -> Result<(), Error> {
+        let ctx_id = ctx.id();
+        let page_count = self.page_count();
+        if page >= page_count {
+            page = 0;
+        }
+        let mut cache = HashMap::new();
+        let mut current = self.load(&mut cache, page).await?;
+
+        // Send the embed with the first page as content
+        let components = self.components(ctx_id, page, &current, false);
+        let has_components = !components.is_empty();
+        let reply =
+            CreateReply::default().embed(self.embed(ctx, page, &current)).components(components);
+        let handle = ctx.send(reply).await?;
+
+        if !has_components {
+            return Ok(());
+        }
+        let mut message = handle.into_message().await?;
+
+        // Loop through incoming interactions with the navigation buttons. A failed press is
+        // reported to whoever pressed it, the rest of the pages keep working
+        while let Some(press) = ComponentInteractionCollector::new(ctx)
+            .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+            // Timeout when nothing has been pressed for 10 minutes
+            .timeout(PAGINATION_TIMEOUT)
+            .await
+        {
+            if let Err(e) =
+                self.press(ctx, &press, &mut message, &mut cache, &mut page, &mut current).await
+            {
+                report_error(ctx, &press, e.to_string()).await;
+            }
+        }
+
+        let edit = EditMessage::new().components(self.components(ctx_id, page, &current, true));
+        if let Err(e) = message.edit(ctx.serenity_context(), edit).await {
+            tracing::warn!("failed to disable pagination buttons: {e}");
+        }
+        Ok(())
     }
+}
 
-    Ok(())
+/// Presses may or may not have been responded to before failing
+async fn report_error(ctx: Context<'_>, press: &ComponentInteraction, error: String) {
+    let response = CreateInteractionResponseMessage::new().content(error.clone()).ephemeral(true);
+    if press
+        .create_response(ctx.serenity_context(), CreateInteractionResponse::Message(response))
+        .await
+        .is_ok()
+    {
+        return;
+    }
+    let followup = CreateInteractionResponseFollowup::new().content(error).ephemeral(true);
+    if let Err(e) = press.create_followup(ctx.serenity_context(), followup).await {
+        tracing::warn!("failed to report pagination error: {e}");
+    }
+}
+
+/// Paginates entries that are all known up front
+pub async fn paginate(
+    ctx: Context<'_>, pages: &[Vec<String>], title: String, page: usize,
+) -> Result<(), Error> {
+    let total_items = pages.iter().map(Vec::len).sum();
+    let page_items = pages.first().map_or(1, Vec::len);
+    Paginator::new(title, total_items, page_items, move |page| {
+        let lines = pages.get(page).cloned().unwrap_or_default();
+        Box::pin(async move { Ok(Page::from(lines)) })
+    })
+    .run(ctx, page)
+    .await
+}
+
+/// Asks the author to confirm with a button, returning the press so the caller can respond
+/// with the outcome. Cancelling and timing out are handled here
+pub async fn confirm(
+    ctx: Context<'_>, embed: CreateEmbed,
+) -> Result<Option<ComponentInteraction>, Error> {
+    let ctx_id = ctx.id();
+    let confirm_button_id = format!("{ctx_id}confirm");
+    let cancel_button_id = format!("{ctx_id}cancel");
+    let buttons = |disabled: bool| {
+        vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(&confirm_button_id)
+                .label("Yes, do it")
+                .style(ButtonStyle::Danger)
+                .disabled(disabled),
+            CreateButton::new(&cancel_button_id)
+                .label("Never mind")
+                .style(ButtonStyle::Secondary)
+                .disabled(disabled),
+        ])]
+    };
+    let handle = ctx.send(CreateReply::default().embed(embed).components(buttons(false))).await?;
+
+    let press = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+    let Some(press) = press else {
+        let mut message = handle.into_message().await?;
+        message.edit(ctx.serenity_context(), EditMessage::new().components(buttons(true))).await?;
+        return Ok(None);
+    };
+    if press.data.custom_id == confirm_button_id {
+        return Ok(Some(press));
+    }
+    let embed =
+        CreateEmbed::new().color(BOT_COLOR).description("O-okay, I won't touch anything then!");
+    press
+        .create_response(
+            ctx.serenity_context(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new().embed(embed).components(Vec::new()),
+            ),
+        )
+        .await?;
+    Ok(None)
 }
 
 pub fn url_guild_id(guild_id: i64) -> String {